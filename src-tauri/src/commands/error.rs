@@ -16,6 +16,9 @@ pub enum AppError {
     #[error("配置错误: {0}")]
     Config(String),
 
+    #[error("写入冲突: {0}")]
+    Conflict(String),
+
     #[error("窗口错误: {0}")]
     Window(String),
 
@@ -31,6 +34,7 @@ impl AppError {
             Self::Image(_) => "IMAGE_ERROR",
             Self::Server(_) => "SERVER_ERROR",
             Self::Config(_) => "CONFIG_ERROR",
+            Self::Conflict(_) => "CONFLICT_ERROR",
             Self::Window(_) => "WINDOW_ERROR",
             Self::Tauri(_) => "TAURI_ERROR",
         }
@@ -1,6 +1,15 @@
-use std::{collections::HashMap, fs, path::Path};
+mod document;
+mod schema;
+mod storage;
 
-use regex::Regex;
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use document::ConfigDocument;
+pub use schema::{ConfigField, TypedEntry, ValidationReport};
+pub use storage::ConfigVersion;
 
 use super::{AppError, AppResult};
 
@@ -25,83 +34,286 @@ fn snake_to_camel(s: &str) -> String {
     result
 }
 
-/// 将小驼峰命名转换为蛇形命名
-fn camel_to_snake(s: &str) -> String {
-    let mut result = String::new();
+/// 配置项的键名转换为前端使用的名称
+/// 已知配置项转换为小驼峰命名，未知的键原样返回，保证写回时能还原为相同的键名
+fn display_key(key: &str) -> String {
+    match schema::field(key) {
+        Some(field) => snake_to_camel(field.key()),
+        None => key.to_string(),
+    }
+}
 
-    for (i, c) in s.chars().enumerate() {
-        if c.is_uppercase() && i > 0 {
-            result.push('_');
-        }
-        result.push(c.to_ascii_lowercase());
+/// 前端使用的名称转换为配置文件中的键名，是 display_key 的逆运算
+fn file_key(name: &str) -> String {
+    schema::FIELDS
+        .iter()
+        .map(|field| field.key())
+        .find(|key| snake_to_camel(key) == name)
+        .unwrap_or(name)
+        .to_string()
+}
+
+/// 获取配置文件路径
+fn config_path(game_path: &str) -> PathBuf {
+    Path::new(game_path).join("game").join("config.txt")
+}
+
+/// 读取并解析配置文件
+fn read_config(game_path: &str) -> AppResult<ConfigDocument> {
+    storage::read(&config_path(game_path)).map(|(document, _)| document)
+}
+
+/// 读取配置文件，修改后原子写回
+/// 写入前确认文件仍是 expected 版本（未提供时为本次读取的版本），否则返回冲突错误
+///
+/// 参数：
+/// - game_path: 游戏目录路径
+/// - expected: 修改所基于的文件版本
+/// - update: 修改配置文档
+///
+/// 返回：
+/// - 成功：写入后的文件版本，内容未变化时不写入并返回原版本
+/// - 失败：错误信息
+fn update_config(
+    game_path: &str,
+    expected: Option<ConfigVersion>,
+    update: impl FnOnce(&mut ConfigDocument) -> AppResult<()>,
+) -> AppResult<ConfigVersion> {
+    let path = config_path(game_path);
+    let (mut document, version) = storage::read(&path)?;
+    version.expect(expected.as_ref())?;
+
+    update(&mut document)?;
+    storage::write(&path, &document, &version)
+}
+
+/// 校验待写入的键名
+/// 键名不能为空或包含换行符、注释符 `;`、分隔符 `:`，否则写回后无法按原样解析
+fn validate_key(key: &str) -> AppResult<()> {
+    if key.trim().is_empty() || key.trim() != key || key.contains([':', ';', '\n', '\r']) {
+        return Err(AppError::Config(format!("无效的配置项名: {key}")));
     }
-    result
+    Ok(())
+}
+
+/// 校验待写入的键和值
+/// 值不能包含换行符或注释符 `;`，已知配置项的值还需符合其类型
+fn validate_entry(key: &str, value: &str) -> AppResult<()> {
+    validate_key(key)?;
+    let accepted = schema::field(key).is_none_or(|field| field.accepts(value));
+    if !accepted || value.contains([';', '\n', '\r']) {
+        return Err(AppError::Config(format!("无效的配置项值: {key}: {value}")));
+    }
+    Ok(())
 }
 
 #[tauri::command]
 pub fn get_game_config(game_path: String) -> AppResult<HashMap<String, String>> {
-    // 构建配置文件路径
-    let config_path = Path::new(&game_path).join("game").join("config.txt");
-
-    // 读取配置文件内容
-    let content = fs::read_to_string(&config_path)?;
-
-    // 解析配置项
-    let mut config_map = HashMap::new();
-    for line in content.lines() {
-        let line = line.trim();
-        if line.is_empty() || !line.ends_with(";") {
-            continue;
-        }
+    let document = read_config(&game_path)?;
 
-        if let Some(line) = line.strip_suffix(";") {
-            if let Some((key, value)) = line.split_once(":") {
-                let key = key.trim().to_string();
-                let value = value.trim().to_string();
-                // 转换为小驼峰命名
-                config_map.insert(snake_to_camel(&key), value);
-            }
-        }
-    }
+    // 按文件顺序插入，重复的键以最后一次出现的值为准
+    let config_map = document
+        .entries()
+        .map(|(key, value)| (display_key(key), value.to_string()))
+        .collect();
 
     Ok(config_map)
 }
 
+/// 设置游戏配置
+/// 已存在的配置项只替换值，其他行保持原样；不存在的配置项追加到文件末尾；
+/// 只校验值有变化的配置项
+///
+/// 参数：
+/// - game_path: 游戏目录路径
+/// - config: 配置项，键可以是配置文件中的键名或 get_game_config 返回的名称
+/// - expected_version: 读取配置时的文件版本，文件已被修改时返回冲突错误；为 None 时不检查
+///
+/// 返回：
+/// - 成功：写入后的文件版本
+/// - 失败：错误信息
 #[tauri::command]
-pub fn set_game_config(game_path: String, config: HashMap<String, String>) -> AppResult<()> {
-    // 构建配置文件路径
-    let config_path = Path::new(&game_path).join("game").join("config.txt");
-
-    // 读取配置文件内容
-    let content = fs::read_to_string(&config_path)?;
-
-    // 使用正则表达式更新配置项
-    let mut updated_content = content.clone();
-    for (key, value) in config.iter() {
-        // 将小驼峰转换为蛇形命名
-        let snake_key = camel_to_snake(key);
-        // 构建正则表达式，匹配键及其值（不区分大小写）
-        let pattern = format!(r"(?i)({}):[^;]*;", regex::escape(&snake_key));
-        let replacement = format!("$1: {};", value);
-
-        // 编译正则表达式
-        let re =
-            Regex::new(&pattern).map_err(|e| AppError::Config(format!("正则表达式错误: {}", e)))?;
-
-        // 如果配置项已存在，则替换；否则添加到文件末尾
-        if re.is_match(&updated_content) {
-            updated_content = re.replace_all(&updated_content, replacement).to_string();
-        } else {
-            // 确保在添加新配置前有一个换行符
-            if !updated_content.is_empty() && !updated_content.ends_with('\n') {
-                updated_content.push('\n');
+pub fn set_game_config(
+    game_path: String,
+    config: HashMap<String, String>,
+    expected_version: Option<ConfigVersion>,
+) -> AppResult<ConfigVersion> {
+    update_config(&game_path, expected_version, |document| {
+        for (name, value) in config.iter() {
+            let key = file_key(name);
+            let value = value.trim();
+            // 前端通常整体回写配置，未修改的值跳过校验，文件中已有的无效值不影响保存其他配置项
+            if document.get(&key) == Some(value) {
+                continue;
             }
-            updated_content.push_str(&format!("{}: {};", snake_key, value));
+            validate_entry(&key, value)?;
+            document.set(&key, value);
         }
-    }
+        Ok(())
+    })
+}
+
+/// 获取配置文件的当前版本，用于之后写入时检测冲突
+///
+/// 参数：
+/// - game_path: 游戏目录路径
+///
+/// 返回：
+/// - 成功：文件版本
+/// - 失败：错误信息
+#[tauri::command]
+pub fn get_game_config_version(game_path: String) -> AppResult<ConfigVersion> {
+    storage::read(&config_path(&game_path)).map(|(_, version)| version)
+}
 
-    // 写入更新后的内容
-    fs::write(&config_path, updated_content)?;
+/// 获取 WebGAL 支持的配置项定义
+///
+/// 返回：配置项定义列表
+#[tauri::command]
+pub fn get_game_config_schema() -> Vec<ConfigField> {
+    schema::FIELDS.to_vec()
+}
 
-    Ok(())
+/// 获取类型化的游戏配置
+/// 与 get_game_config 不同，返回配置文件中的原始键名，并保留重复的键和行号
+///
+/// 参数：
+/// - game_path: 游戏目录路径
+///
+/// 返回：
+/// - 成功：按文件顺序排列的配置项
+/// - 失败：错误信息
+#[tauri::command]
+pub fn get_game_config_entries(game_path: String) -> AppResult<Vec<TypedEntry>> {
+    let document = read_config(&game_path)?;
+    Ok(schema::typed_entries(&document))
+}
+
+/// 校验游戏配置
+/// 检查必填项、重复或未知的键、布尔值与可选值，以及标题图片、标题音乐等引用的文件是否存在
+///
+/// 参数：
+/// - game_path: 游戏目录路径
+///
+/// 返回：
+/// - 成功：校验报告
+/// - 失败：错误信息
+#[tauri::command]
+pub fn validate_game_config(game_path: String) -> AppResult<ValidationReport> {
+    let document = read_config(&game_path)?;
+    let game_dir = Path::new(&game_path).join("game");
+    Ok(schema::validate(&document, &game_dir))
+}
+
+/// 删除配置项
+/// 键重复时全部删除，其他行保持原样
+///
+/// 参数：
+/// - game_path: 游戏目录路径
+/// - keys: 待删除的键，可以是配置文件中的键名或 get_game_config 返回的名称
+/// - expected_version: 读取配置时的文件版本，文件已被修改时返回冲突错误；为 None 时不检查
+///
+/// 返回：
+/// - 成功：写入后的文件版本
+/// - 失败：错误信息
+#[tauri::command]
+pub fn remove_game_config(
+    game_path: String,
+    keys: Vec<String>,
+    expected_version: Option<ConfigVersion>,
+) -> AppResult<ConfigVersion> {
+    update_config(&game_path, expected_version, |document| {
+        for name in &keys {
+            document.remove(&file_key(name));
+        }
+        Ok(())
+    })
+}
+
+/// 重命名配置项
+/// 只替换键名，值、注释与格式保持不变；键重复时全部重命名
+///
+/// 参数：
+/// - game_path: 游戏目录路径
+/// - from: 原键名，可以是配置文件中的键名或 get_game_config 返回的名称
+/// - to: 新键名，规则同上
+/// - expected_version: 读取配置时的文件版本，文件已被修改时返回冲突错误；为 None 时不检查
+///
+/// 返回：
+/// - 成功：写入后的文件版本
+/// - 失败：错误信息，原键不存在或新键已存在时失败
+#[tauri::command]
+pub fn rename_game_config_key(
+    game_path: String,
+    from: String,
+    to: String,
+    expected_version: Option<ConfigVersion>,
+) -> AppResult<ConfigVersion> {
+    let (from, to) = (file_key(&from), file_key(&to));
+    validate_key(&to)?;
+
+    update_config(&game_path, expected_version, |document| {
+        if from == to {
+            return Ok(());
+        }
+        if document.get(&to).is_some() {
+            return Err(AppError::Config(format!("配置项已存在: {to}")));
+        }
+        if document.rename(&from, &to) == 0 {
+            return Err(AppError::Config(format!("配置项不存在: {from}")));
+        }
+        Ok(())
+    })
+}
+
+/// 获取以 `|` 分隔的列表值
+///
+/// 参数：
+/// - game_path: 游戏目录路径
+/// - key: 键名，可以是配置文件中的键名或 get_game_config 返回的名称
+///
+/// 返回：
+/// - 成功：列表项，配置项不存在时为 None
+/// - 失败：错误信息
+#[tauri::command]
+pub fn get_game_config_list(game_path: String, key: String) -> AppResult<Option<Vec<String>>> {
+    let document = read_config(&game_path)?;
+    Ok(document.get_list(&file_key(&key)))
+}
+
+/// 设置以 `|` 分隔的列表值
+/// 配置项已存在时沿用原有的分隔格式，否则追加到文件末尾
+///
+/// 参数：
+/// - game_path: 游戏目录路径
+/// - key: 键名，可以是配置文件中的键名或 get_game_config 返回的名称
+/// - items: 列表项，不能为空或包含 `|`；与现有列表相同时不校验也不写入
+/// - expected_version: 读取配置时的文件版本，文件已被修改时返回冲突错误；为 None 时不检查
+///
+/// 返回：
+/// - 成功：写入后的文件版本
+/// - 失败：错误信息
+#[tauri::command]
+pub fn set_game_config_list(
+    game_path: String,
+    key: String,
+    items: Vec<String>,
+    expected_version: Option<ConfigVersion>,
+) -> AppResult<ConfigVersion> {
+    let key = file_key(&key);
+    let items: Vec<_> = items.iter().map(|item| item.trim().to_string()).collect();
+
+    update_config(&game_path, expected_version, |document| {
+        if document.get_list(&key).as_ref() == Some(&items) {
+            return Ok(());
+        }
+        for item in &items {
+            if item.is_empty() || item.contains(document::LIST_SEPARATOR) {
+                return Err(AppError::Config(format!("无效的列表项: {key}: {item}")));
+            }
+            validate_entry(&key, item)?;
+        }
+        document.set_list(&key, &items);
+        Ok(())
+    })
 }
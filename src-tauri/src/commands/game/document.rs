@@ -0,0 +1,340 @@
+// 配置文档模块：无损解析游戏的 config.txt，供配置命令读取和修改
+// 主要功能：
+// 1. 无损解析：保留注释、空行、换行符与重复键，未修改的内容按原样写回
+// 2. 精确匹配：配置项按完整键名查找，不会匹配到同前缀的其他键
+// 3. 局部修改：修改配置项时只替换值所在的片段，新配置项追加到文件末尾
+// 4. 删除与重命名：删除配置项所在的行，或只替换键名
+// 5. 列表值：以 `|` 分隔的多个值按数组读写，沿用原有的分隔格式
+
+use std::ops::Range;
+
+/// 文件开头可能存在的 UTF-8 BOM
+const BOM: char = '\u{feff}';
+
+/// 列表值的分隔符
+pub(super) const LIST_SEPARATOR: char = '|';
+
+/// 配置文件中的一行
+/// 包含：
+/// - text: 该行的原始文本，包含行尾换行符
+/// - entry: 该行为配置项时，键和值在 text 中的位置
+#[derive(Debug, Clone)]
+struct ConfigLine {
+    text: String,
+    entry: Option<EntrySpan>,
+}
+
+/// 配置项在行文本中的位置
+/// 包含：
+/// - key: 键名（已去除首尾空白）
+/// - value: 值（已去除首尾空白），值为空时是 `:` 之后的空区间
+#[derive(Debug, Clone)]
+struct EntrySpan {
+    key: Range<usize>,
+    value: Range<usize>,
+}
+
+impl ConfigLine {
+    /// 解析一行文本
+    /// 形如 `键:值;注释` 的行为配置项，`;` 及之后的内容视为注释；
+    /// 以 `;` 开头、空行或不含 `:` 的行原样保留
+    fn parse(text: String) -> Self {
+        let body = text.trim_end_matches(['\r', '\n']);
+        let code = match body.find(';') {
+            Some(end) => &body[..end],
+            None => body,
+        };
+
+        let entry = code.find(':').and_then(|colon| {
+            let key = trimmed_range(code, 0..colon, true);
+            let value = trimmed_range(code, colon + 1..code.len(), false);
+            (!key.is_empty()).then_some(EntrySpan {
+                key,
+                value,
+            })
+        });
+
+        Self {
+            text,
+            entry,
+        }
+    }
+
+    fn key(&self) -> Option<&str> {
+        self.entry
+            .as_ref()
+            .map(|entry| &self.text[entry.key.clone()])
+    }
+
+    fn value(&self) -> Option<&str> {
+        self.entry
+            .as_ref()
+            .map(|entry| &self.text[entry.value.clone()])
+    }
+
+    fn has_newline(&self) -> bool {
+        self.text.ends_with('\n')
+    }
+
+    /// 替换配置项的值，键、分隔符、注释与换行符保持不变
+    fn set_value(&mut self, value: &str) {
+        let Some(entry) = self.entry.as_mut() else {
+            return;
+        };
+        self.text.replace_range(entry.value.clone(), value);
+        entry.value.end = entry.value.start + value.len();
+    }
+
+    /// 替换配置项的键名，值、分隔符、注释与换行符保持不变
+    fn set_key(&mut self, key: &str) {
+        let Some(entry) = self.entry.as_mut() else {
+            return;
+        };
+        self.text.replace_range(entry.key.clone(), key);
+        let end = entry.key.start + key.len();
+        let shift = |offset: usize| offset + end - entry.key.end;
+        entry.value = shift(entry.value.start)..shift(entry.value.end);
+        entry.key.end = end;
+    }
+}
+
+/// 拆分列表值，忽略空项
+pub(super) fn split_list(value: &str) -> Vec<String> {
+    value
+        .split(LIST_SEPARATOR)
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// 计算去除首尾空白后的区间，key 为 true 时同时去除开头的 BOM
+fn trimmed_range(text: &str, range: Range<usize>, key: bool) -> Range<usize> {
+    let slice = &text[range.clone()];
+    let start_trimmed = match key {
+        true => slice.trim_start_matches(|c: char| c == BOM || c.is_whitespace()),
+        false => slice.trim_start(),
+    };
+    let start = range.start + (slice.len() - start_trimmed.len());
+    let end = start + start_trimmed.trim_end().len();
+    start..end
+}
+
+/// 配置文档
+/// 按行保存 config.txt 的原始内容，`to_string` 的结果与读入内容逐字节一致，
+/// 修改只影响被修改的配置项所在的行
+#[derive(Debug, Clone, Default)]
+pub(super) struct ConfigDocument {
+    lines: Vec<ConfigLine>,
+}
+
+impl ConfigDocument {
+    /// 解析配置文件内容
+    pub(super) fn parse(content: &str) -> Self {
+        Self {
+            lines: content
+                .split_inclusive('\n')
+                .map(|line| ConfigLine::parse(line.to_string()))
+                .collect(),
+        }
+    }
+
+    /// 按文件顺序遍历配置项的键和值，重复的键会出现多次
+    pub(super) fn entries(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.numbered_entries().map(|(_, key, value)| (key, value))
+    }
+
+    /// 按文件顺序遍历配置项的行号（从 1 开始）、键和值
+    pub(super) fn numbered_entries(&self) -> impl Iterator<Item = (usize, &str, &str)> {
+        self.lines
+            .iter()
+            .enumerate()
+            .filter_map(|(index, line)| Some((index + 1, line.key()?, line.value()?)))
+    }
+
+    /// 获取配置项的值
+    /// 键重复时与 WebGAL 一致，以最后一次出现的值为准
+    pub(super) fn get(&self, key: &str) -> Option<&str> {
+        self.entries()
+            .filter(|(k, _)| *k == key)
+            .last()
+            .map(|(_, value)| value)
+    }
+
+    /// 设置配置项的值
+    /// 配置项已存在时只替换生效的（最后一次出现的）值，否则在文件末尾追加 `键:值;`
+    pub(super) fn set(&mut self, key: &str, value: &str) {
+        if let Some(line) = self
+            .lines
+            .iter_mut()
+            .rev()
+            .find(|line| line.key() == Some(key))
+        {
+            line.set_value(value);
+            return;
+        }
+
+        // 原文件末尾没有换行符时，追加的配置项同样不以换行符结尾
+        let newline = self.newline();
+        let line_end = match self.lines.last_mut() {
+            Some(last) if !last.has_newline() => {
+                last.text.push_str(newline);
+                ""
+            }
+            _ => newline,
+        };
+        self.lines
+            .push(ConfigLine::parse(format!("{key}:{value};{line_end}")));
+    }
+
+    /// 删除配置项，重复的键全部删除
+    ///
+    /// 返回：删除的行数
+    pub(super) fn remove(&mut self, key: &str) -> usize {
+        let count = self.lines.len();
+        let trailing_newline = self.lines.last().is_none_or(ConfigLine::has_newline);
+
+        self.lines.retain(|line| line.key() != Some(key));
+
+        // 删除了没有换行符的最后一行时，新的最后一行同样去掉换行符
+        if !trailing_newline {
+            if let Some(last) = self.lines.last_mut() {
+                let body_len = last.text.trim_end_matches(['\r', '\n']).len();
+                last.text.truncate(body_len);
+            }
+        }
+
+        count - self.lines.len()
+    }
+
+    /// 重命名配置项，重复的键全部重命名
+    ///
+    /// 返回：重命名的行数
+    pub(super) fn rename(&mut self, from: &str, to: &str) -> usize {
+        self.lines
+            .iter_mut()
+            .filter(|line| line.key() == Some(from))
+            .map(|line| line.set_key(to))
+            .count()
+    }
+
+    /// 获取列表值，以 `|` 分隔，忽略空项
+    pub(super) fn get_list(&self, key: &str) -> Option<Vec<String>> {
+        self.get(key).map(split_list)
+    }
+
+    /// 设置列表值
+    /// 原有的值在 `|` 两侧带空格时，写入时同样带空格
+    pub(super) fn set_list(&mut self, key: &str, items: &[String]) {
+        let spaced = self.get(key).is_some_and(|value| {
+            value.contains(&format!(" {LIST_SEPARATOR}"))
+                || value.contains(&format!("{LIST_SEPARATOR} "))
+        });
+        let separator = match spaced {
+            true => format!(" {LIST_SEPARATOR} "),
+            false => LIST_SEPARATOR.to_string(),
+        };
+        self.set(key, &items.join(&separator));
+    }
+
+    /// 文档使用的换行符，沿用第一行的换行符，默认为 `\n`
+    fn newline(&self) -> &'static str {
+        match self.lines.first() {
+            Some(line) if line.text.ends_with("\r\n") => "\r\n",
+            _ => "\n",
+        }
+    }
+}
+
+impl std::fmt::Display for ConfigDocument {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.lines
+            .iter()
+            .try_for_each(|line| f.write_str(&line.text))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ConfigDocument;
+
+    fn assert_round_trip(content: &str) {
+        assert_eq!(ConfigDocument::parse(content).to_string(), content);
+    }
+
+    #[test]
+    fn round_trip_preserves_comments() {
+        assert_round_trip(
+            "; 游戏配置\nGame_name:欢迎使用WebGAL！; 游戏名称\n;Title_img:old.png;\n",
+        );
+    }
+
+    #[test]
+    fn round_trip_preserves_blank_lines() {
+        assert_round_trip("\nGame_name:a;\n\n\n   \nGame_key:b;\n\n");
+    }
+
+    #[test]
+    fn round_trip_preserves_crlf() {
+        assert_round_trip("Game_name:a;\r\n\r\nGame_key:b;\r\n");
+    }
+
+    #[test]
+    fn round_trip_preserves_bom() {
+        let content = "\u{feff}Game_name:a;\nGame_key:b;\n";
+        assert_round_trip(content);
+        assert_eq!(ConfigDocument::parse(content).get("Game_name"), Some("a"));
+    }
+
+    #[test]
+    fn round_trip_without_final_newline() {
+        assert_round_trip("Game_name:a;\nGame_key:b;");
+        assert_round_trip("Game_name:a");
+    }
+
+    #[test]
+    fn round_trip_preserves_duplicate_keys() {
+        let content = "Game_name:first;\nGame_name:second;\n";
+        assert_round_trip(content);
+        assert_eq!(
+            ConfigDocument::parse(content).get("Game_name"),
+            Some("second")
+        );
+    }
+
+    #[test]
+    fn round_trip_preserves_empty_value() {
+        let content = "Title_bgm:;\nGame_name:a;\n";
+        assert_round_trip(content);
+        assert_eq!(ConfigDocument::parse(content).get("Title_bgm"), Some(""));
+    }
+
+    #[test]
+    fn set_matches_exact_key_only() {
+        let mut document = ConfigDocument::parse("Game_name_extra:x;\nGame_name:a;\n");
+        document.set("Game_name", "b");
+        assert_eq!(document.to_string(), "Game_name_extra:x;\nGame_name:b;\n");
+    }
+
+    #[test]
+    fn set_edits_last_duplicate_only() {
+        let mut document = ConfigDocument::parse(
+            "Game_name:first; 旧值\nGame_key:k;\nGame_name : second ; 生效\n",
+        );
+        document.set("Game_name", "third");
+        assert_eq!(
+            document.to_string(),
+            "Game_name:first; 旧值\nGame_key:k;\nGame_name : third ; 生效\n"
+        );
+    }
+
+    #[test]
+    fn set_appends_missing_key() {
+        let mut document = ConfigDocument::parse("Game_name:a;\r\nGame_key:b;");
+        document.set("Title_img", "title.png");
+        assert_eq!(
+            document.to_string(),
+            "Game_name:a;\r\nGame_key:b;\r\nTitle_img:title.png;"
+        );
+    }
+}
@@ -0,0 +1,367 @@
+// 配置结构模块：描述 WebGAL 支持的配置项，并校验 config.txt
+// 主要功能：
+// 1. 配置项定义：已知配置项的键名、值类型（文本、布尔、可选值、列表、文件、文件列表）与是否必填
+// 2. 类型化读取：按配置项定义将值解析为布尔值或列表
+// 3. 配置校验：检查缺失的必填项、重复或未知的键、无效的值，以及引用的标题图片、标题音乐是否存在
+
+use std::{collections::HashMap, path::Path};
+
+use serde::Serialize;
+
+use super::document::{split_list, ConfigDocument};
+
+/// 配置项的值类型
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase", tag = "type")]
+pub enum ConfigKind {
+    /// 任意文本
+    Text,
+    /// `true` 或 `false`
+    Bool,
+    /// 固定的可选值之一
+    Choice { options: &'static [&'static str] },
+    /// game/ 下指定目录中的文件
+    File { dir: &'static str },
+    /// 以 `|` 分隔的多个文本
+    List,
+    /// 以 `|` 分隔的多个文件，均位于 game/ 下的指定目录
+    FileList { dir: &'static str },
+}
+
+/// 配置项定义
+/// 包含：
+/// - key: 配置文件中的键名，区分大小写
+/// - kind: 值类型
+/// - required: 是否必填
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigField {
+    key: &'static str,
+    kind: ConfigKind,
+    required: bool,
+}
+
+impl ConfigField {
+    const fn new(key: &'static str, kind: ConfigKind) -> Self {
+        Self {
+            key,
+            kind,
+            required: false,
+        }
+    }
+
+    const fn required(mut self) -> Self {
+        self.required = true;
+        self
+    }
+
+    pub(super) fn key(&self) -> &'static str {
+        self.key
+    }
+
+    /// 值是否符合布尔值或可选值的要求，其他类型的配置项不做限制
+    pub(super) fn accepts(&self, value: &str) -> bool {
+        match self.kind {
+            ConfigKind::Bool => parse_bool(value).is_some(),
+            ConfigKind::Choice {
+                options,
+            } => options.contains(&value),
+            _ => true,
+        }
+    }
+}
+
+/// WebGAL 支持的配置项
+pub(super) const FIELDS: &[ConfigField] = &[
+    ConfigField::new("Game_name", ConfigKind::Text).required(),
+    ConfigField::new("Game_key", ConfigKind::Text).required(),
+    ConfigField::new("Package_name", ConfigKind::Text),
+    ConfigField::new("Description", ConfigKind::Text),
+    ConfigField::new(
+        "Title_img",
+        ConfigKind::FileList {
+            dir: "background",
+        },
+    ),
+    ConfigField::new(
+        "Title_bgm",
+        ConfigKind::File {
+            dir: "bgm",
+        },
+    ),
+    ConfigField::new(
+        "Game_Logo",
+        ConfigKind::FileList {
+            dir: "background",
+        },
+    ),
+    ConfigField::new(
+        "Textbox_theme",
+        ConfigKind::Choice {
+            options: &["standard", "imss"],
+        },
+    ),
+    ConfigField::new("Default_Language", ConfigKind::List),
+    ConfigField::new("Enable_Appreciation", ConfigKind::Bool),
+    ConfigField::new("Legacy_Expression_Blend_Mode", ConfigKind::Bool),
+    ConfigField::new("Show_panic", ConfigKind::Bool),
+    ConfigField::new("Steam_AppID", ConfigKind::Text),
+];
+
+/// 按键名查找配置项定义，区分大小写
+pub(super) fn field(key: &str) -> Option<&'static ConfigField> {
+    FIELDS.iter().find(|field| field.key == key)
+}
+
+/// 类型化的配置值
+/// 值不符合配置项定义时按文本返回，并在校验报告中给出问题
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase", tag = "type", content = "value")]
+pub enum ConfigValue {
+    Text(String),
+    Bool(bool),
+    List(Vec<String>),
+}
+
+impl ConfigValue {
+    /// 按值类型解析配置值
+    fn parse(kind: Option<ConfigKind>, value: &str) -> Self {
+        match kind {
+            Some(ConfigKind::Bool) => match parse_bool(value) {
+                Some(value) => Self::Bool(value),
+                None => Self::Text(value.to_string()),
+            },
+            Some(
+                ConfigKind::List
+                | ConfigKind::FileList {
+                    ..
+                },
+            ) => Self::List(split_list(value)),
+            _ => Self::Text(value.to_string()),
+        }
+    }
+}
+
+/// 类型化的配置项
+/// 包含：
+/// - key: 键名
+/// - line: 所在行号（从 1 开始）
+/// - value: 类型化的值
+/// - kind: 配置项的值类型，未知的键为 None
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TypedEntry {
+    key: String,
+    line: usize,
+    value: ConfigValue,
+    kind: Option<ConfigKind>,
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    match value {
+        "true" => Some(true),
+        "false" => Some(false),
+        _ => None,
+    }
+}
+
+/// 按文件顺序读取全部配置项的类型化值，重复的键会出现多次
+pub(super) fn typed_entries(document: &ConfigDocument) -> Vec<TypedEntry> {
+    document
+        .numbered_entries()
+        .map(|(line, key, value)| {
+            let kind = field(key).map(|field| field.kind);
+            TypedEntry {
+                key: key.to_string(),
+                line,
+                value: ConfigValue::parse(kind, value),
+                kind,
+            }
+        })
+        .collect()
+}
+
+/// 问题的严重程度
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum IssueSeverity {
+    /// 会导致游戏行为异常
+    Error,
+    /// 可能是笔误，WebGAL 会忽略或以最后一次出现的值为准
+    Warning,
+}
+
+/// 校验问题的类型
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase", tag = "type")]
+pub enum IssueKind {
+    /// 缺少必填项
+    MissingKey,
+    /// 键重复出现，first_line 为首次出现的行号
+    #[serde(rename_all = "camelCase")]
+    DuplicateKey { first_line: usize },
+    /// 未知的键，suggestion 为仅大小写不同的已知键名
+    UnknownKey { suggestion: Option<&'static str> },
+    /// 布尔值不是 `true` 或 `false`
+    InvalidBool { value: String },
+    /// 值不在可选值中
+    InvalidChoice {
+        value: String,
+        options: &'static [&'static str],
+    },
+    /// 引用的文件不存在，path 为相对于 game/ 的路径
+    MissingFile { path: String },
+}
+
+/// 校验问题
+/// 包含：
+/// - key: 相关的键名
+/// - line: 所在行号，缺少必填项时为 None
+/// - severity: 严重程度
+/// - issue: 问题类型与详情
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigIssue {
+    key: String,
+    line: Option<usize>,
+    severity: IssueSeverity,
+    issue: IssueKind,
+}
+
+/// 配置校验报告
+/// 包含：
+/// - valid: 是否没有错误级别的问题
+/// - issues: 按行号排序的问题列表
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ValidationReport {
+    valid: bool,
+    issues: Vec<ConfigIssue>,
+}
+
+/// 校验配置文档
+///
+/// 参数：
+/// - document: 配置文档
+/// - game_dir: 游戏的 game/ 目录，用于检查引用的文件
+///
+/// 返回：校验报告
+pub(super) fn validate(document: &ConfigDocument, game_dir: &Path) -> ValidationReport {
+    let mut issues = Vec::new();
+    let mut issue = |key: &str, line: Option<usize>, severity, issue| {
+        issues.push(ConfigIssue {
+            key: key.to_string(),
+            line,
+            severity,
+            issue,
+        });
+    };
+
+    for field in FIELDS.iter().filter(|field| field.required) {
+        if document.get(field.key).is_none() {
+            issue(field.key, None, IssueSeverity::Error, IssueKind::MissingKey);
+        }
+    }
+
+    let mut first_lines = HashMap::new();
+    for (line, key, value) in document.numbered_entries() {
+        if let Some(&first_line) = first_lines.get(key) {
+            issue(
+                key,
+                Some(line),
+                IssueSeverity::Warning,
+                IssueKind::DuplicateKey {
+                    first_line,
+                },
+            );
+        } else {
+            first_lines.insert(key, line);
+        }
+
+        let Some(schema) = field(key) else {
+            let suggestion = FIELDS
+                .iter()
+                .find(|field| field.key.eq_ignore_ascii_case(key))
+                .map(|field| field.key);
+            issue(
+                key,
+                Some(line),
+                IssueSeverity::Warning,
+                IssueKind::UnknownKey {
+                    suggestion,
+                },
+            );
+            continue;
+        };
+
+        let (dir, names) = match schema.kind {
+            ConfigKind::Text | ConfigKind::List => continue,
+            ConfigKind::Bool => {
+                if parse_bool(value).is_none() {
+                    let value = value.to_string();
+                    issue(
+                        key,
+                        Some(line),
+                        IssueSeverity::Error,
+                        IssueKind::InvalidBool {
+                            value,
+                        },
+                    );
+                }
+                continue;
+            }
+            ConfigKind::Choice {
+                options,
+            } => {
+                if !options.contains(&value) {
+                    let value = value.to_string();
+                    issue(
+                        key,
+                        Some(line),
+                        IssueSeverity::Error,
+                        IssueKind::InvalidChoice {
+                            value,
+                            options,
+                        },
+                    );
+                }
+                continue;
+            }
+            ConfigKind::File {
+                dir,
+            } => (dir, vec![value.to_string()]),
+            ConfigKind::FileList {
+                dir,
+            } => (dir, split_list(value)),
+        };
+
+        for name in names
+            .iter()
+            .filter(|name| !name.is_empty() && !is_remote(name))
+        {
+            if !game_dir.join(dir).join(name).is_file() {
+                issue(
+                    key,
+                    Some(line),
+                    IssueSeverity::Error,
+                    IssueKind::MissingFile {
+                        path: format!("{dir}/{name}"),
+                    },
+                );
+            }
+        }
+    }
+
+    issues.sort_by_key(|issue| issue.line);
+    ValidationReport {
+        valid: issues
+            .iter()
+            .all(|issue| issue.severity != IssueSeverity::Error),
+        issues,
+    }
+}
+
+/// 是否为远程地址，远程资源不检查是否存在
+fn is_remote(name: &str) -> bool {
+    name.contains("://")
+}
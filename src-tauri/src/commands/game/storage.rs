@@ -0,0 +1,242 @@
+// 配置存储模块：安全地读写 config.txt
+// 主要功能：
+// 1. 版本标识：以修改时间和内容哈希标识配置文件的版本
+// 2. 冲突检测：写入前确认文件仍是读取时的版本，被外部修改时返回冲突错误
+// 3. 原子写入：先写入同目录的临时文件再重命名，写入中途崩溃不会截断配置文件
+// 4. 滚动备份：写入前将原文件保存为 .bak，保留最近几份
+
+use std::{
+    fs::{self, File},
+    io::Write,
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::document::ConfigDocument;
+use crate::commands::{AppError, AppResult};
+
+/// 保留的备份数量：config.txt.bak、config.txt.bak.1、config.txt.bak.2
+const MAX_BACKUPS: usize = 3;
+
+/// 配置文件版本
+/// 包含：
+/// - modified: 文件修改时间（Unix 毫秒），无法获取时为 None
+/// - hash: 文件内容的 blake3 哈希
+///
+/// 判断文件是否被修改时以内容哈希为准，只有修改时间变化（如被 touch）不视为冲突
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigVersion {
+    modified: Option<u64>,
+    hash: String,
+}
+
+impl ConfigVersion {
+    fn of(path: &Path, content: &[u8]) -> Self {
+        let modified = fs::metadata(path)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .map(|duration| duration.as_millis() as u64);
+
+        Self {
+            modified,
+            hash: blake3::hash(content).to_hex().to_string(),
+        }
+    }
+
+    /// 确认文件仍是期望的版本
+    pub(super) fn expect(&self, expected: Option<&ConfigVersion>) -> AppResult<()> {
+        match expected {
+            Some(expected) if expected.hash != self.hash => Err(AppError::Conflict(format!(
+                "配置文件已被修改（期望版本 {}，当前版本 {}），请重新读取后再保存",
+                short_hash(&expected.hash),
+                short_hash(&self.hash)
+            ))),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// 用于错误信息的短哈希
+fn short_hash(hash: &str) -> &str {
+    &hash[..hash.len().min(12)]
+}
+
+/// 读取并解析配置文件，同时返回文件版本
+pub(super) fn read(path: &Path) -> AppResult<(ConfigDocument, ConfigVersion)> {
+    let content = fs::read_to_string(path)?;
+    let version = ConfigVersion::of(path, content.as_bytes());
+    Ok((ConfigDocument::parse(&content), version))
+}
+
+/// 写入配置文件
+///
+/// 参数：
+/// - path: 配置文件路径
+/// - document: 配置文档
+/// - base: 修改所基于的文件版本，文件已不是该版本时返回冲突错误
+///
+/// 返回：
+/// - 成功：写入后的文件版本
+/// - 失败：错误信息
+pub(super) fn write(
+    path: &Path,
+    document: &ConfigDocument,
+    base: &ConfigVersion,
+) -> AppResult<ConfigVersion> {
+    let current = fs::read(path)?;
+    ConfigVersion::of(path, &current).expect(Some(base))?;
+
+    let content = document.to_string();
+    if content.as_bytes() == current {
+        return Ok(base.clone());
+    }
+
+    rotate_backups(path, &current)?;
+
+    let temp = temp_path(path)?;
+    if let Err(e) = write_synced(&temp, content.as_bytes()).and_then(|()| fs::rename(&temp, path)) {
+        let _ = fs::remove_file(&temp);
+        return Err(e.into());
+    }
+
+    Ok(ConfigVersion::of(path, content.as_bytes()))
+}
+
+/// 写入文件并等待数据落盘
+fn write_synced(path: &Path, content: &[u8]) -> std::io::Result<()> {
+    let mut file = File::create(path)?;
+    file.write_all(content)?;
+    file.sync_all()
+}
+
+/// 同目录下的临时文件路径，重命名时不会跨文件系统
+fn temp_path(path: &Path) -> AppResult<PathBuf> {
+    let mut suffix = [0u8; 6];
+    getrandom::fill(&mut suffix)
+        .map_err(|e| AppError::Config(format!("生成临时文件名失败: {e}")))?;
+    let suffix: String = suffix.iter().map(|byte| format!("{byte:02x}")).collect();
+    Ok(with_suffix(path, &format!(".{suffix}.tmp")))
+}
+
+/// 第 index 份备份的路径，0 为最新的备份
+fn backup_path(path: &Path, index: usize) -> PathBuf {
+    match index {
+        0 => with_suffix(path, ".bak"),
+        _ => with_suffix(path, &format!(".bak.{index}")),
+    }
+}
+
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(suffix);
+    path.with_file_name(name)
+}
+
+/// 滚动备份：依次后移已有的备份，并将当前内容写为最新的备份
+fn rotate_backups(path: &Path, current: &[u8]) -> AppResult<()> {
+    for index in (1..MAX_BACKUPS).rev() {
+        let older = backup_path(path, index - 1);
+        if older.exists() {
+            fs::rename(&older, backup_path(path, index))?;
+        }
+    }
+    fs::write(backup_path(path, 0), current)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::PathBuf};
+
+    use super::{backup_path, read, write, ConfigDocument};
+    use crate::commands::AppError;
+
+    /// 在临时目录中创建内容为 content 的配置文件
+    fn config_file(name: &str, content: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "webgal-craft-storage-{name}-{}",
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("config.txt");
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    fn document(content: &str) -> ConfigDocument {
+        ConfigDocument::parse(content)
+    }
+
+    #[test]
+    fn rejects_stale_version() {
+        let path = config_file("conflict", "Game_name:a;\n");
+        let (_, version) = read(&path).unwrap();
+        fs::write(&path, "Game_name:b;\n").unwrap();
+
+        let result = write(&path, &document("Game_name:c;\n"), &version);
+        assert!(matches!(result, Err(AppError::Conflict(_))));
+        assert_eq!(fs::read_to_string(&path).unwrap(), "Game_name:b;\n");
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn rotates_backups() {
+        let path = config_file("backups", "Game_name:0;\n");
+        for index in 1..=4 {
+            let (_, version) = read(&path).unwrap();
+            write(&path, &document(&format!("Game_name:{index};\n")), &version).unwrap();
+        }
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "Game_name:4;\n");
+        for (index, expected) in ["Game_name:3;\n", "Game_name:2;\n", "Game_name:1;\n"]
+            .iter()
+            .enumerate()
+        {
+            assert_eq!(
+                fs::read_to_string(backup_path(&path, index)).unwrap(),
+                *expected
+            );
+        }
+        assert!(!backup_path(&path, 3).exists());
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn writes_through_temp_file() {
+        let path = config_file("atomic", "Game_name:a;\n");
+        let (_, version) = read(&path).unwrap();
+        let version = write(&path, &document("Game_name:b;\n"), &version).unwrap();
+
+        let (saved, current) = read(&path).unwrap();
+        assert_eq!(saved.get("Game_name"), Some("b"));
+        assert_eq!(current, version);
+
+        let dir = path.parent().unwrap();
+        let leftovers: Vec<_> = fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .filter(|name| name.ends_with(".tmp"))
+            .collect();
+        assert!(leftovers.is_empty(), "残留临时文件: {leftovers:?}");
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn skips_unchanged_content() {
+        let path = config_file("unchanged", "Game_name:a;\n");
+        let (document, version) = read(&path).unwrap();
+
+        assert_eq!(write(&path, &document, &version).unwrap(), version);
+        assert!(!backup_path(&path, 0).exists());
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+}
@@ -1,6 +1,7 @@
 pub mod error;
 pub mod fs;
 pub mod game;
+pub mod scene;
 pub mod server;
 pub mod thumbnail;
 pub mod window;
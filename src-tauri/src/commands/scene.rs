@@ -0,0 +1,90 @@
+mod parser;
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+pub use parser::Scene;
+use serde::Serialize;
+
+use super::AppResult;
+
+/// 场景文件扩展名
+const SCENE_EXTENSION: &str = "txt";
+
+/// 已解析的场景文件
+/// - path: 相对于 game/scene/ 的路径，以 `/` 分隔
+/// - scene: 解析结果
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SceneFile {
+    path: String,
+    scene: Scene,
+}
+
+/// 递归收集目录中的场景文件
+fn collect_scene_files(dir: &Path, files: &mut Vec<PathBuf>) -> AppResult<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_scene_files(&path, files)?;
+        } else if path.extension().is_some_and(|ext| ext == SCENE_EXTENSION) {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// 读取并解析场景文件，非 UTF-8 内容按替换字符处理
+fn read_scene(path: &Path) -> AppResult<Scene> {
+    let bytes = fs::read(path)?;
+    Ok(parser::parse_scene(&String::from_utf8_lossy(&bytes)))
+}
+
+/// 解析场景文本
+///
+/// 参数：
+/// - content: 场景文件内容
+///
+/// 返回：解析后的场景
+#[tauri::command]
+pub fn parse_scene(content: String) -> Scene {
+    parser::parse_scene(&content)
+}
+
+/// 解析游戏的全部场景文件
+/// 在后端一次性解析 game/scene/ 下的所有场景，供批量分析使用，无需逐个载入编辑器
+///
+/// 参数：
+/// - game_path: 游戏目录路径
+///
+/// 返回：
+/// - 成功：按路径排序的场景解析结果
+/// - 失败：错误信息
+#[tauri::command]
+pub async fn parse_game_scenes(game_path: String) -> AppResult<Vec<SceneFile>> {
+    let scene_dir = Path::new(&game_path).join("game").join("scene");
+
+    let mut files = Vec::new();
+    collect_scene_files(&scene_dir, &mut files)?;
+
+    let mut scenes = files
+        .iter()
+        .map(|file| {
+            let relative = file.strip_prefix(&scene_dir).unwrap_or(file);
+            let path = relative
+                .components()
+                .map(|component| component.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            Ok(SceneFile {
+                path,
+                scene: read_scene(file)?,
+            })
+        })
+        .collect::<AppResult<Vec<_>>>()?;
+    scenes.sort_by(|a, b| a.path.cmp(&b.path));
+
+    Ok(scenes)
+}
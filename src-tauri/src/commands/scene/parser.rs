@@ -0,0 +1,671 @@
+// 场景解析模块：在 Rust 侧解析 WebGAL 场景脚本，供批量分析整个项目的命令使用
+// 主要功能：
+// 1. 语句解析：命令、内容、参数（-next、-id=、-when= 等）与行内注释，与 WebGAL 的解析规则一致
+// 2. 角色简写：`角色:台词` 解析为带说话人的 say 语句，`:台词` 为旁白，无冒号的台词沿用上一位说话人
+// 3. 多行语句：以空白加 `-` 或 `|` 开头的行接续上一条语句
+// 4. 位置信息：语句、命令、内容与每个参数在源文本中的行列位置
+
+use serde::Serialize;
+
+/// 文件开头可能存在的 UTF-8 BOM
+const BOM: char = '\u{feff}';
+
+/// WebGAL 命令
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Command {
+    Say,
+    ChangeBg,
+    ChangeFigure,
+    Bgm,
+    PlayVideo,
+    PixiPerform,
+    PixiInit,
+    Intro,
+    MiniAvatar,
+    ChangeScene,
+    Choose,
+    End,
+    SetComplexAnimation,
+    SetFilter,
+    Label,
+    JumpLabel,
+    SetVar,
+    CallScene,
+    ShowVars,
+    UnlockCg,
+    UnlockBgm,
+    FilmMode,
+    SetTextbox,
+    SetAnimation,
+    PlayEffect,
+    SetTempAnimation,
+    SetTransform,
+    SetTransition,
+    GetUserInput,
+    ApplyStyle,
+    Wait,
+    Comment,
+}
+
+impl Command {
+    /// 按命令名查找命令，区分大小写；comment 只由注释行产生，不能作为命令名使用
+    fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "say" => Self::Say,
+            "changeBg" => Self::ChangeBg,
+            "changeFigure" => Self::ChangeFigure,
+            "bgm" => Self::Bgm,
+            "playVideo" => Self::PlayVideo,
+            "pixiPerform" => Self::PixiPerform,
+            "pixiInit" => Self::PixiInit,
+            "intro" => Self::Intro,
+            "miniAvatar" => Self::MiniAvatar,
+            "changeScene" => Self::ChangeScene,
+            "choose" => Self::Choose,
+            "end" => Self::End,
+            "setComplexAnimation" => Self::SetComplexAnimation,
+            "setFilter" => Self::SetFilter,
+            "label" => Self::Label,
+            "jumpLabel" => Self::JumpLabel,
+            "setVar" => Self::SetVar,
+            "callScene" => Self::CallScene,
+            "showVars" => Self::ShowVars,
+            "unlockCg" => Self::UnlockCg,
+            "unlockBgm" => Self::UnlockBgm,
+            "filmMode" => Self::FilmMode,
+            "setTextbox" => Self::SetTextbox,
+            "setAnimation" => Self::SetAnimation,
+            "playEffect" => Self::PlayEffect,
+            "setTempAnimation" => Self::SetTempAnimation,
+            "setTransform" => Self::SetTransform,
+            "setTransition" => Self::SetTransition,
+            "getUserInput" => Self::GetUserInput,
+            "applyStyle" => Self::ApplyStyle,
+            "wait" => Self::Wait,
+            _ => return None,
+        })
+    }
+}
+
+/// 源文本中的位置
+/// - line: 行号，从 1 开始
+/// - column: 列号，从 1 开始，按字符计数
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Position {
+    line: usize,
+    column: usize,
+}
+
+/// 源文本中的区间，end 指向区间之后的位置
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Span {
+    start: Position,
+    end: Position,
+}
+
+/// 参数值
+/// 与 WebGAL 一致：`true`/`false` 为布尔值，数字为数值，只有键名的参数（如 `-next`）为 true
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum ArgValue {
+    Bool(bool),
+    Number(f64),
+    String(String),
+}
+
+impl ArgValue {
+    fn parse(value: Option<&str>) -> Self {
+        let Some(value) = value else {
+            return Self::Bool(true);
+        };
+        match value {
+            "true" => Self::Bool(true),
+            "false" => Self::Bool(false),
+            _ => match value.parse::<f64>() {
+                Ok(number) if number.is_finite() => Self::Number(number),
+                _ => Self::String(value.to_string()),
+            },
+        }
+    }
+}
+
+/// 语句参数
+/// - key: 参数名
+/// - value: 参数值
+/// - span: 参数在源文本中的位置，不含前导的 ` -`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Arg {
+    key: String,
+    value: ArgValue,
+    span: Span,
+}
+
+/// 语句
+/// 包含：
+/// - index: 语句序号，与 WebGAL 运行时的语句ID一致（即首行的行号减 1）
+/// - command: 命令
+/// - command_raw: 冒号前的原始命令文本，角色简写时为角色名
+/// - speaker: say 语句的说话人；`角色:` 简写为角色名，`:` 为空字符串（旁白），
+///   `say:` 为 -speaker 参数的值，未指定说话人（沿用上一位说话人）时为 None
+/// - content: 内容，`\;` 已还原为 `;`
+/// - args: 参数，按书写顺序排列
+/// - inline_comment: 行内注释，注释行的注释内容在 content 中
+/// - span: 整条语句的位置，多行语句包含所有接续行
+/// - command_span: 命令的位置，无冒号的台词为 None
+/// - content_span: 内容的位置
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Sentence {
+    index: usize,
+    command: Command,
+    command_raw: String,
+    speaker: Option<String>,
+    content: String,
+    args: Vec<Arg>,
+    inline_comment: String,
+    span: Span,
+    command_span: Option<Span>,
+    content_span: Span,
+}
+
+/// 场景
+/// - sentences: 语句列表，不含空行和接续行
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Scene {
+    sentences: Vec<Sentence>,
+}
+
+/// 逻辑语句中的一段源文本
+/// - offset: 在逻辑语句文本中的起始字节偏移
+/// - line: 所在源文本行的下标
+/// - column: 在该行中的起始字节偏移
+struct Segment {
+    offset: usize,
+    line: usize,
+    column: usize,
+}
+
+/// 逻辑语句：首行与其接续行拼接后的文本，以及到源文本位置的映射
+struct LogicalLine<'a> {
+    index: usize,
+    text: String,
+    segments: Vec<Segment>,
+    lines: &'a [&'a str],
+}
+
+impl LogicalLine<'_> {
+    /// 将逻辑语句中的字节偏移转换为源文本位置
+    /// 区间终点落在两段的交界处时归属前一段，避免终点跳到下一行行首
+    fn position(&self, offset: usize, is_end: bool) -> Position {
+        let segment = self
+            .segments
+            .iter()
+            .rev()
+            .find(|segment| match is_end {
+                true => segment.offset < offset || segment.offset == 0,
+                false => segment.offset <= offset,
+            })
+            .unwrap_or(&self.segments[0]);
+
+        let line = self.lines[segment.line];
+        let byte = (segment.column + offset - segment.offset).min(line.len());
+        Position {
+            line: segment.line + 1,
+            column: line[..byte].chars().count() + 1,
+        }
+    }
+
+    fn span(&self, start: usize, end: usize) -> Span {
+        Span {
+            start: self.position(start, false),
+            end: self.position(end, true),
+        }
+    }
+}
+
+/// 是否为接续行：以空白开头，去除空白后以 `-` 或 `|` 开头
+fn is_continuation(line: &str) -> bool {
+    line.starts_with(char::is_whitespace) && line.trim_start().starts_with(['-', '|'])
+}
+
+/// 将场景文本拆分为逻辑语句
+/// 空行打断接续，接续行按 `-` 前补空格、`|` 直接拼接的规则并入上一条语句
+fn logical_lines<'a>(lines: &'a [&'a str]) -> Vec<LogicalLine<'a>> {
+    let mut logical: Vec<LogicalLine> = Vec::new();
+    let mut continuable = false;
+
+    for (index, line) in lines.iter().enumerate() {
+        if line.trim().is_empty() {
+            continuable = false;
+            continue;
+        }
+
+        if continuable && is_continuation(line) {
+            let Some(last) = logical.last_mut() else {
+                continue;
+            };
+            let trimmed = line.trim();
+            if trimmed.starts_with('-') {
+                last.text.push(' ');
+            }
+            last.segments.push(Segment {
+                offset: last.text.len(),
+                line: index,
+                column: line.len() - line.trim_start().len(),
+            });
+            last.text.push_str(trimmed);
+            continue;
+        }
+
+        continuable = true;
+        logical.push(LogicalLine {
+            index,
+            text: line.to_string(),
+            segments: vec![Segment {
+                offset: 0,
+                line: index,
+                column: 0,
+            }],
+            lines,
+        });
+    }
+
+    logical
+}
+
+/// 查找第一个未转义（前面不是 `\`）的 `;`
+fn find_unescaped_semicolon(text: &str) -> Option<usize> {
+    text.match_indices(';')
+        .map(|(index, _)| index)
+        .find(|&index| !text[..index].ends_with('\\'))
+}
+
+/// 计算区间内去除首尾空白后的区间
+fn trim_range(text: &str, start: usize, end: usize) -> (usize, usize) {
+    let slice = &text[start..end];
+    let trimmed_start = start + (slice.len() - slice.trim_start().len());
+    (trimmed_start, trimmed_start + slice.trim().len())
+}
+
+/// 解析参数区域，参数以 ` -` 分隔
+fn parse_args(line: &LogicalLine, start: usize, end: usize) -> Vec<Arg> {
+    let mut args = Vec::new();
+    let text = &line.text[..end];
+    let mut cursor = start;
+
+    while cursor < end {
+        // 跳过分隔符 ` -`
+        let arg_start = cursor + " -".len();
+        let arg_end = text[arg_start.min(end)..]
+            .find(" -")
+            .map_or(end, |found| arg_start + found);
+        cursor = arg_end;
+        if arg_start >= arg_end {
+            continue;
+        }
+
+        let (arg_start, arg_end) = trim_range(text, arg_start, arg_end);
+        let raw = &text[arg_start..arg_end];
+        let (key, value) = match raw.split_once('=') {
+            Some((key, value)) => (key, Some(value)),
+            None => (raw, None),
+        };
+        if key.is_empty() {
+            continue;
+        }
+
+        args.push(Arg {
+            key: key.to_string(),
+            value: ArgValue::parse(value),
+            span: line.span(arg_start, arg_end),
+        });
+    }
+
+    args
+}
+
+/// 解析一条逻辑语句
+fn parse_sentence(line: &LogicalLine) -> Sentence {
+    let text = line.text.as_str();
+    let span = line.span(0, text.len());
+
+    // 行内注释：第一个未转义的 `;` 之后，到下一个未转义的 `;` 为止
+    let code_end = find_unescaped_semicolon(text).unwrap_or(text.len());
+    let comment = (code_end < text.len()).then(|| {
+        let start = code_end + 1;
+        let end = find_unescaped_semicolon(&text[start..]).map_or(text.len(), |end| start + end);
+        (start, end)
+    });
+    let inline_comment = comment.map_or(String::new(), |(start, end)| text[start..end].to_string());
+
+    // 注释行
+    if text[..code_end].trim().is_empty() {
+        let (start, end) = comment.unwrap_or((code_end, code_end));
+        return Sentence {
+            index: line.index,
+            command: Command::Comment,
+            command_raw: "comment".to_string(),
+            speaker: None,
+            content: inline_comment,
+            args: Vec::new(),
+            inline_comment: String::new(),
+            span,
+            command_span: None,
+            content_span: line.span(start, end),
+        };
+    }
+
+    let code = &text[..code_end];
+    let colon = code.find(':');
+    let body_start = colon.map_or(0, |colon| colon + 1);
+    let args_start = code[body_start..]
+        .find(" -")
+        .map_or(code_end, |found| body_start + found);
+    let args = parse_args(line, args_start, code_end);
+    let (content_start, content_end) = trim_range(text, body_start, args_start);
+    let body = &text[content_start..content_end];
+
+    let (command, command_raw, speaker, content, command_span) = match colon {
+        Some(colon) => {
+            let command_raw = &code[..colon];
+            let command_span = Some(line.span(0, colon));
+            match Command::from_name(command_raw) {
+                Some(Command::Say) => {
+                    let speaker =
+                        args.iter()
+                            .find(|arg| arg.key == "speaker")
+                            .map(|arg| match &arg.value {
+                                ArgValue::String(name) => name.clone(),
+                                ArgValue::Number(number) => number.to_string(),
+                                ArgValue::Bool(_) => String::new(),
+                            });
+                    (Command::Say, command_raw, speaker, body, command_span)
+                }
+                Some(command) => (command, command_raw, None, body, command_span),
+                // 角色简写：冒号前为角色名
+                None => (
+                    Command::Say,
+                    command_raw,
+                    Some(command_raw.to_string()),
+                    body,
+                    command_span,
+                ),
+            }
+        }
+        // 无冒号：整条为命令名（如 `end`）或沿用上一位说话人的台词
+        None => match Command::from_name(body) {
+            Some(command) => (
+                command,
+                body,
+                None,
+                "",
+                Some(line.span(content_start, content_end)),
+            ),
+            None => (Command::Say, "", None, body, None),
+        },
+    };
+
+    let content_span = match content.is_empty() && colon.is_none() {
+        true => line.span(content_end, content_end),
+        false => line.span(content_start, content_end),
+    };
+
+    Sentence {
+        index: line.index,
+        command,
+        command_raw: command_raw.to_string(),
+        speaker,
+        content: content.replace("\\;", ";"),
+        args,
+        inline_comment,
+        span,
+        command_span,
+        content_span,
+    }
+}
+
+/// 解析场景文本
+///
+/// 参数：
+/// - text: 场景文件内容
+///
+/// 返回：解析后的场景，空行与接续行不产生语句，语句序号与 WebGAL 运行时一致
+pub fn parse_scene(text: &str) -> Scene {
+    let text = text.strip_prefix(BOM).unwrap_or(text);
+    let lines: Vec<&str> = text
+        .split('\n')
+        .map(|line| line.strip_suffix('\r').unwrap_or(line))
+        .collect();
+
+    Scene {
+        sentences: logical_lines(&lines).iter().map(parse_sentence).collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_scene, ArgValue, Command, Position, Scene, Sentence, Span};
+
+    /// 读取场景夹具，夹具为纯文本场景文件，前端 helper/webgal-script 的测试可直接复用
+    macro_rules! fixture {
+        ($name:literal) => {
+            parse_scene(include_str!(concat!(
+                env!("CARGO_MANIFEST_DIR"),
+                "/tests/fixtures/webgal-script/",
+                $name
+            )))
+        };
+    }
+
+    fn span(
+        (start_line, start_column): (usize, usize),
+        (end_line, end_column): (usize, usize),
+    ) -> Span {
+        Span {
+            start: Position {
+                line: start_line,
+                column: start_column,
+            },
+            end: Position {
+                line: end_line,
+                column: end_column,
+            },
+        }
+    }
+
+    fn sentence(scene: &Scene, index: usize) -> &Sentence {
+        scene
+            .sentences
+            .iter()
+            .find(|sentence| sentence.index == index)
+            .unwrap_or_else(|| panic!("没有序号为 {index} 的语句"))
+    }
+
+    fn arg_spans(sentence: &Sentence) -> Vec<(&str, &ArgValue, Span)> {
+        sentence
+            .args
+            .iter()
+            .map(|arg| (arg.key.as_str(), &arg.value, arg.span))
+            .collect()
+    }
+
+    #[test]
+    fn parses_args() {
+        let scene = fixture!("args.txt");
+        assert_eq!(scene.sentences.len(), 4);
+
+        let bg = sentence(&scene, 0);
+        assert_eq!(bg.command, Command::ChangeBg);
+        assert_eq!(bg.content, "bg.webp");
+        assert_eq!(bg.content_span, span((1, 10), (1, 17)));
+        assert_eq!(
+            arg_spans(bg),
+            [("next", &ArgValue::Bool(true), span((1, 19), (1, 23)))]
+        );
+
+        let figure = sentence(&scene, 1);
+        assert_eq!(figure.command, Command::ChangeFigure);
+        assert_eq!(figure.command_span, Some(span((2, 1), (2, 13))));
+        assert_eq!(figure.content_span, span((2, 14), (2, 23)));
+        assert_eq!(
+            arg_spans(figure),
+            [
+                ("left", &ArgValue::Bool(true), span((2, 25), (2, 29))),
+                (
+                    "id",
+                    &ArgValue::String("alice".to_string()),
+                    span((2, 31), (2, 39))
+                ),
+                ("next", &ArgValue::Bool(true), span((2, 41), (2, 45))),
+            ]
+        );
+
+        let say = sentence(&scene, 2);
+        assert_eq!(say.content, "早上好。");
+        assert_eq!(say.content_span, span((3, 7), (3, 11)));
+        assert_eq!(
+            arg_spans(say),
+            [
+                (
+                    "when",
+                    &ArgValue::String("affection>3".to_string()),
+                    span((3, 13), (3, 29))
+                ),
+                (
+                    "vocal",
+                    &ArgValue::String("v1.mp3".to_string()),
+                    span((3, 31), (3, 43))
+                ),
+            ]
+        );
+
+        // `=` 只按第一个拆分，内容中的 `=` 保留
+        let set_var = sentence(&scene, 3);
+        assert_eq!(set_var.command, Command::SetVar);
+        assert_eq!(set_var.content, "affection=affection+1");
+        assert_eq!(set_var.content_span, span((4, 8), (4, 29)));
+    }
+
+    #[test]
+    fn parses_speakers() {
+        let scene = fixture!("speakers.txt");
+
+        let named = sentence(&scene, 0);
+        assert_eq!(named.command, Command::Say);
+        assert_eq!(named.speaker.as_deref(), Some("Alice"));
+        assert_eq!(named.command_span, Some(span((1, 1), (1, 6))));
+        assert_eq!(named.content_span, span((1, 7), (1, 19)));
+
+        let narration = sentence(&scene, 1);
+        assert_eq!(narration.command, Command::Say);
+        assert_eq!(narration.speaker.as_deref(), Some(""));
+        assert_eq!(narration.content, "窗外下起了雨。");
+        assert_eq!(narration.content_span, span((2, 2), (2, 9)));
+
+        let continued = sentence(&scene, 2);
+        assert_eq!(continued.command, Command::Say);
+        assert_eq!(continued.speaker, None);
+        assert_eq!(continued.command_span, None);
+        assert_eq!(continued.content_span, span((3, 1), (3, 9)));
+
+        let explicit = sentence(&scene, 3);
+        assert_eq!(explicit.command_raw, "say");
+        assert_eq!(explicit.speaker.as_deref(), Some("Bob"));
+        assert_eq!(explicit.content_span, span((4, 5), (4, 12)));
+    }
+
+    #[test]
+    fn parses_comments_and_escapes() {
+        let scene = fixture!("comments.txt");
+
+        let comment = sentence(&scene, 0);
+        assert_eq!(comment.command, Command::Comment);
+        assert_eq!(comment.content, " 第一章");
+        assert_eq!(comment.content_span, span((1, 2), (1, 6)));
+
+        let inline = sentence(&scene, 1);
+        assert_eq!(inline.content, "这句话有注释");
+        assert_eq!(inline.inline_comment, "注释内容");
+        assert_eq!(inline.span, span((2, 1), (2, 18)));
+
+        // `\;` 还原为 `;`，位置仍按源文本计算
+        let escaped = sentence(&scene, 2);
+        assert_eq!(escaped.content, "分号要转义;才能显示");
+        assert_eq!(escaped.inline_comment, "");
+        assert_eq!(escaped.content_span, span((3, 7), (3, 18)));
+        assert_eq!(
+            arg_spans(escaped),
+            [("next", &ArgValue::Bool(true), span((3, 20), (3, 24)))]
+        );
+
+        let change_scene = sentence(&scene, 3);
+        assert_eq!(change_scene.command, Command::ChangeScene);
+        assert_eq!(change_scene.content, "chapter2.txt");
+        assert_eq!(change_scene.inline_comment, "跳转");
+    }
+
+    #[test]
+    fn parses_continuation_lines() {
+        let scene = fixture!("multiline.txt");
+        let indexes: Vec<_> = scene
+            .sentences
+            .iter()
+            .map(|sentence| sentence.index)
+            .collect();
+        assert_eq!(indexes, [0, 4, 7]);
+
+        let choose = sentence(&scene, 0);
+        assert_eq!(choose.command, Command::Choose);
+        assert_eq!(
+            choose.content,
+            "去学校:school.txt|回家:home.txt|去公园:park.txt"
+        );
+        assert_eq!(choose.content_span, span((1, 8), (3, 16)));
+        assert_eq!(choose.span, span((1, 1), (3, 16)));
+
+        let figure = sentence(&scene, 4);
+        assert_eq!(figure.content, "alice.png");
+        assert_eq!(figure.span, span((5, 1), (7, 8)));
+        assert_eq!(
+            arg_spans(figure),
+            [
+                ("left", &ArgValue::Bool(true), span((6, 4), (6, 8))),
+                ("next", &ArgValue::Bool(true), span((7, 4), (7, 8))),
+            ]
+        );
+
+        let end = sentence(&scene, 7);
+        assert_eq!(end.command, Command::End);
+        assert_eq!(end.command_span, Some(span((8, 1), (8, 4))));
+    }
+
+    #[test]
+    fn parses_bom_and_crlf() {
+        let scene = fixture!("bom-crlf.txt");
+        let indexes: Vec<_> = scene
+            .sentences
+            .iter()
+            .map(|sentence| sentence.index)
+            .collect();
+        assert_eq!(indexes, [0, 1, 3]);
+
+        let first = sentence(&scene, 0);
+        assert_eq!(first.speaker.as_deref(), Some("Alice"));
+        assert_eq!(first.command_span, Some(span((1, 1), (1, 6))));
+        assert_eq!(first.content, "第一行");
+        assert_eq!(first.content_span, span((1, 7), (1, 10)));
+
+        let second = sentence(&scene, 1);
+        assert_eq!(second.content, "第二行");
+        assert_eq!(second.span, span((2, 1), (2, 11)));
+        assert_eq!(
+            arg_spans(second),
+            [("next", &ArgValue::Bool(true), span((2, 7), (2, 11)))]
+        );
+
+        assert_eq!(sentence(&scene, 3).command, Command::End);
+    }
+}
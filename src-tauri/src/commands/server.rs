@@ -1,86 +1,492 @@
 // 服务器模块：提供静态文件服务和WebSocket通信功能
 // 主要功能：
 // 1. 静态文件服务：支持多个静态站点托管
-// 2. WebSocket通信：支持广播和单播消息
+// 2. WebSocket通信：支持全局广播、按站点广播和单播消息
 // 3. 服务器管理：启动、停止和状态管理
+// 4. 局域网预览：为局域网设备签发访问令牌（见 lan 子模块）
+// 5. 实时刷新：监听站点目录及其附加目录，文件变化时推送给预览客户端（见 watcher 子模块）
+// 6. 服务器守护：异常退出通知与自动重启（见 supervisor 子模块）
+// 7. 站点注册表：稳定的站点ID、站点别名与持久化（见 registry 子模块）
+// 8. 源隔离：通过 `{站点ID}.localhost` 子域名为每个站点提供独立的源（见 origin 子模块）
+// 9. 内存覆盖层：优先提供编辑器中尚未保存的文件内容（见 overlay 子模块）
+// 10. 请求诊断：记录失败请求并汇总缺失文件（见 diagnostics 子模块）
+// 11. 访问日志：按需记录每个请求的大小与耗时（见 access_log 子模块）
+// 12. 网络模拟：按站点模拟带宽限制、延迟和随机失败（见 throttle 子模块）
+// 13. 站点选项：按站点配置压缩、跨源隔离、CORS 与缓存模式（见 options 子模块）
+// 14. 调试协议：强类型的 WebGAL 调试消息与二进制帧（见 protocol 子模块）
+// 15. 心跳检测：定时 Ping 客户端并断开失去响应的连接（见 heartbeat 子模块）
+// 16. 消息背压：有界的客户端队列、落后统计与场景状态重放（见 backpressure 子模块）
+// 17. 会话录制：录制 /api/webgalsync 上的消息并重放到新的预览（见 recorder 子模块）
+// 18. 分层目录：站点目录之后依次查找附加目录，使游戏可链接共享引擎（见 layers 子模块）
+// 19. 编辑器桥接：向站点首页注入脚本，上报场景、错误、控制台与资源耗时（见 bridge 子模块）
+// 20. 预览存档：将预览中的存档同步到项目目录，并提供存档管理（见 saves 子模块）
+// 21. 运行时状态：按客户端保存预览上报的变量、场景与舞台状态（见 inspector 子模块）
+
+pub mod access_log;
+pub mod backpressure;
+mod bridge;
+pub mod diagnostics;
+mod heartbeat;
+pub mod inspector;
+pub mod lan;
+mod layers;
+pub mod options;
+pub mod origin;
+pub mod overlay;
+mod protocol;
+pub mod recorder;
+mod registry;
+pub mod saves;
+mod supervisor;
+pub mod throttle;
+mod watcher;
 
 use std::{
     collections::HashMap,
-    hash::{Hash, Hasher},
+    convert::Infallible,
     net::SocketAddr,
     path::PathBuf,
     sync::Arc,
+    time::{Instant, SystemTime, UNIX_EPOCH},
 };
 
 use axum::{
     extract::{
         ws::{Message, WebSocket, WebSocketUpgrade},
-        ConnectInfo, Path as AxumPath, State as AxumState,
+        ConnectInfo, DefaultBodyLimit, Path as AxumPath, Query, State as AxumState,
+    },
+    http::{
+        header::{CACHE_CONTROL, COOKIE, SET_COOKIE, USER_AGENT},
+        HeaderMap, HeaderValue, Request, StatusCode, Uri,
     },
-    http::{header::CACHE_CONTROL, HeaderValue, Request, StatusCode, Uri},
-    response::{IntoResponse, Redirect},
-    routing::get,
+    middleware,
+    response::{IntoResponse, Redirect, Response},
+    routing::{get, put},
     Router,
 };
 use futures::{SinkExt, StreamExt};
-use portpicker::pick_unused_port;
+use serde::{Deserialize, Serialize};
 use tauri::{ipc::Channel, State as TauriState};
 use tokio::{
-    net::TcpListener,
-    sync::{broadcast, mpsc, oneshot, Mutex, RwLock},
+    sync::{broadcast, broadcast::error::RecvError, mpsc, oneshot, watch, Mutex, RwLock},
     task::JoinHandle,
+    time::MissedTickBehavior,
 };
 use tower::util::ServiceExt;
-use tower_http::{services::ServeDir, set_header::SetResponseHeaderLayer};
+use tower_http::{compression::Compression, set_header::SetResponseHeaderLayer};
 
+pub use self::{
+    backpressure::ClientQueueStats,
+    bridge::BridgeReport,
+    heartbeat::{DisconnectReason, HeartbeatOptions},
+    options::SiteOptions,
+    protocol::{ClientPayload, DebugMessage},
+    registry::SiteEntry,
+    supervisor::ServerEvent,
+};
+use self::{
+    heartbeat::Liveness,
+    options::NO_STORE,
+    protocol::BinaryFrame,
+    recorder::{Direction, Target},
+    registry::SiteRegistry,
+    supervisor::{ListenInfo, SupervisorOptions},
+};
 use super::{AppError, AppResult};
 
 /// 应用程序状态
 /// 包含：
-/// - sites: 静态站点映射表，键为站点哈希，值为(路径, 服务实例)元组
+/// - sites: 静态站点映射表，键为站点ID（站点哈希或别名）
 /// - broadcast_tx: 广播消息发送器，用于向所有客户端发送消息
-/// - unicast_clients: WebSocket客户端映射表，用于单播消息发送
+/// - rooms: 站点房间映射表，键为站点哈希，值为仅面向该站点客户端的广播发送器
+/// - unicast_clients: WebSocket客户端映射表，键为客户端ID
+/// - access: 局域网预览的访问控制
+/// - watchers: 站点目录监听器映射表，键为站点哈希
+/// - overlays: 站点内存覆盖层，键为站点哈希，值为尚未保存的文件内容
+/// - diagnostics: 各站点的失败请求记录与订阅通道
+/// - access_log: 访问日志的开关、环形缓冲区与订阅通道
+/// - throttles: 站点网络限速配置，键为站点哈希
+/// - latest_scenes: 编辑器最近发出的场景状态，用于重新同步落后的客户端
+/// - sessions: 会话录制与重放状态
+/// - inspector: 各客户端的运行时状态快照与订阅通道
 struct AppState {
-    sites: RwLock<HashMap<String, (PathBuf, ServeDir)>>,
+    sites: RwLock<HashMap<String, StaticSite>>,
     // 广播通道用于高效广播
     broadcast_tx: broadcast::Sender<Message>,
+    // 按站点划分的广播房间
+    rooms: RwLock<HashMap<String, broadcast::Sender<Message>>>,
     // 独立通道映射用于单播
-    unicast_clients: Mutex<HashMap<SocketAddr, mpsc::UnboundedSender<Message>>>,
+    unicast_clients: Mutex<HashMap<String, UnicastClient>>,
+    // 局域网预览的访问令牌
+    access: RwLock<lan::AccessControl>,
+    // 文件变化监听
+    watchers: Mutex<HashMap<String, watcher::SiteWatcher>>,
+    // 编辑器中尚未保存的文件
+    overlays: RwLock<overlay::Overlays>,
+    // 失败请求记录
+    diagnostics: Mutex<diagnostics::Diagnostics>,
+    // 访问日志
+    access_log: Mutex<access_log::AccessLog>,
+    // 网络模拟
+    throttles: RwLock<HashMap<String, throttle::ThrottleProfile>>,
+    // 最近的场景状态
+    latest_scenes: Mutex<backpressure::LatestScenes>,
+    // 会话录制与重放
+    sessions: Mutex<recorder::Sessions>,
+    // 运行时状态
+    inspector: Mutex<inspector::Inspector>,
+}
+
+/// 静态站点
+/// 包含：
+/// - path: 标准化后的站点目录
+/// - layers: 在站点目录之后依次查找的附加目录（如共享的引擎目录）
+/// - serve_dir: 分层服务目录实例
+/// - options: 站点选项
+struct StaticSite {
+    path: PathBuf,
+    layers: Vec<PathBuf>,
+    serve_dir: layers::LayeredServeDir,
+    options: SiteOptions,
+}
+
+/// 单播客户端
+/// 包含：
+/// - info: 客户端身份信息
+/// - tx: 单播消息发送器，队列容量有限
+/// - stats: 客户端消息统计
+struct UnicastClient {
+    info: ClientInfo,
+    tx: mpsc::Sender<Message>,
+    stats: Arc<backpressure::ClientStats>,
+}
+
+/// WebSocket客户端身份信息
+/// 包含：
+/// - id: 客户端ID，每个连接随机生成，不随地址复用而变化
+/// - addr: 客户端地址
+/// - site: 所属站点哈希，无法识别时为 None
+/// - user_agent: 客户端 User-Agent
+/// - connected_at: 连接时间（Unix 毫秒）
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClientInfo {
+    id: String,
+    addr: SocketAddr,
+    site: Option<String>,
+    user_agent: Option<String>,
+    connected_at: u64,
+}
+
+/// 客户端消息信封
+/// 将客户端发来的已校验消息连同客户端身份一起转发给编辑器
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClientMessage {
+    client: ClientInfo,
+    message: ClientPayload,
+}
+
+/// 连接建立后发给客户端的握手消息，告知其客户端ID
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase", tag = "event", content = "data")]
+enum HandshakeMessage<'a> {
+    Connected { client_id: &'a str },
+}
+
+/// WebSocket 连接的查询参数
+/// - site: 客户端所属的站点ID，供无法通过子域名识别站点的连接（如编辑器中的跨站 iframe）显式指定
+/// - bridge: 存在时为桥接脚本自行建立的上报连接
+/// - client_id: 客户端上次连接时分配的ID，重连时沿用
+#[derive(Debug, Default, Deserialize)]
+struct WsQuery {
+    site: Option<String>,
+    bridge: Option<String>,
+    client_id: Option<String>,
+}
+
+/// WebSocket 连接的处理配置
+/// - on_message: 消息处理通道
+/// - on_event: 服务器事件通道，用于通知客户端连接与断开
+/// - heartbeat: 心跳配置
+#[derive(Clone)]
+struct WsContext {
+    on_message: Channel<ClientMessage>,
+    on_event: Channel<ServerEvent>,
+    heartbeat: HeartbeatOptions,
+}
+
+/// 客户端ID的随机字节数
+const CLIENT_ID_BYTES: usize = 8;
+
+/// 标记 WebSocket 客户端所属站点的 Cookie 名
+/// 在返回站点首页时写入，且仅对 /api/webgalsync 生效
+const SITE_COOKIE_NAME: &str = "webgal_craft_site";
+
+/// 广播通道容量
+const BROADCAST_CAPACITY: usize = 100;
+
+impl AppState {
+    /// 获取站点房间的广播发送器，不存在时创建
+    async fn join_room(&self, hash: &str) -> broadcast::Sender<Message> {
+        self.rooms
+            .write()
+            .await
+            .entry(hash.to_string())
+            .or_insert_with(|| broadcast::channel(BROADCAST_CAPACITY).0)
+            .clone()
+    }
+
+    /// 向站点房间广播消息
+    /// 房间不存在或没有客户端时返回错误
+    async fn broadcast_to_room(&self, hash: &str, message: Message) -> AppResult<()> {
+        self.rooms
+            .read()
+            .await
+            .get(hash)
+            .ok_or_else(|| AppError::Server("Broadcast failed".into()))?
+            .send(message)
+            .map_err(|_| AppError::Server("Broadcast failed".into()))?;
+        Ok(())
+    }
+
+    /// 向指定客户端发送调试消息，并记录到正在录制的会话
+    async fn send_to_client(&self, client_id: &str, message: &DebugMessage) -> AppResult<()> {
+        let clients = self.unicast_clients.lock().await;
+        let client = clients
+            .get(client_id)
+            .ok_or_else(|| AppError::Server("Client not found".into()))?;
+
+        let message = Message::Text(message.to_json()?.into());
+        self.sessions.lock().await.record(
+            Direction::ToGame,
+            Target::Client(client_id.to_string()),
+            &message,
+        );
+        client.try_send(message)
+    }
+
+    /// 挂载静态站点并监听其目录，站点已挂载时仅更新站点选项和附加目录
+    ///
+    /// 参数：
+    /// - hash: 站点ID
+    /// - path: 标准化后的站点目录
+    /// - options: 站点选项，为 None 时保留已有选项
+    /// - layers: 标准化后的附加目录，为 None 时保留已有附加目录
+    async fn mount_site(
+        self: &Arc<Self>,
+        hash: &str,
+        path: PathBuf,
+        options: Option<SiteOptions>,
+        layers: Option<Vec<PathBuf>>,
+    ) {
+        let mut sites = self.sites.write().await;
+
+        if let Some(site) = sites.get_mut(hash) {
+            if let Some(options) = options {
+                site.options = options;
+            }
+            if let Some(layers) = layers {
+                site.serve_dir = layers::LayeredServeDir::new(&site.path, &layers);
+                // 附加目录变化时重新监听，旧的监听器随替换一并停止
+                if site.layers != layers {
+                    self.watch_site(hash, site.path.clone(), &layers).await;
+                }
+                site.layers = layers;
+            }
+            return;
+        }
+
+        // 创建服务目录实例
+        let layers = layers.unwrap_or_default();
+        self.watch_site(hash, path.clone(), &layers).await;
+        sites.insert(
+            hash.to_string(),
+            StaticSite {
+                path: path.clone(),
+                serve_dir: layers::LayeredServeDir::new(&path, &layers),
+                layers,
+                options: options.unwrap_or_default(),
+            },
+        );
+    }
+
+    /// 监听站点目录及其附加目录，替换该站点已有的监听器
+    /// 监听失败不影响站点访问，仅失去实时刷新能力
+    async fn watch_site(self: &Arc<Self>, hash: &str, path: PathBuf, layers: &[PathBuf]) {
+        let mut watchers = self.watchers.lock().await;
+        match watcher::SiteWatcher::spawn(self.clone(), hash.to_string(), path, layers) {
+            Ok(site_watcher) => {
+                watchers.insert(hash.to_string(), site_watcher);
+            }
+            Err(e) => {
+                watchers.remove(hash);
+                log::warn!("站点 {hash} 文件监听启动失败: {e}");
+            }
+        }
+    }
+
+    /// 卸载静态站点，停止文件监听，撤销该站点的预览会话并清除其覆盖层文件与网络限速配置
+    ///
+    /// 参数：
+    /// - hash: 站点ID
+    ///
+    /// 返回：站点此前已挂载时返回该站点
+    async fn unmount_site(&self, hash: &str) -> Option<StaticSite> {
+        let site = self.sites.write().await.remove(hash);
+
+        self.watchers.lock().await.remove(hash);
+        self.rooms.write().await.remove(hash);
+        self.overlays.write().await.remove(hash);
+        self.throttles.write().await.remove(hash);
+        self.diagnostics.lock().await.clear_site(hash);
+        self.access.write().await.revoke_site(hash);
+        self.latest_scenes.lock().await.clear_site(hash);
+
+        site
+    }
 }
 
 /// 服务器状态管理
 /// 包含：
 /// - app_state: 应用程序状态，包含站点信息和消息通道
 /// - server_handle: 服务器运行句柄，用于控制服务器生命周期
-/// - server_address: 服务器监听地址，用于客户端连接
+/// - registry: 站点注册表，记录站点目录与站点ID的对应关系
 pub struct ServerState {
     app_state: Arc<AppState>,
     server_handle: Option<ServerHandle>,
-    server_address: Option<SocketAddr>,
+    registry: SiteRegistry,
 }
 
 /// 服务器控制句柄
 /// 用于管理服务器的生命周期，包含：
-/// - join_handle: 异步任务句柄，用于等待服务器关闭
+/// - join_handle: 守护任务句柄，用于等待服务器关闭
 /// - shutdown_tx: 关闭信号发送器，用于触发服务器优雅关闭
+/// - listen_rx: 监听信息接收器，自动重启后地址可能变化
 struct ServerHandle {
     join_handle: JoinHandle<()>,
     shutdown_tx: oneshot::Sender<()>,
+    listen_rx: watch::Receiver<ListenInfo>,
+}
+
+impl ServerState {
+    /// 创建服务器状态，并从指定文件加载站点注册表
+    pub fn with_registry(registry_file: PathBuf) -> Self {
+        Self {
+            registry: SiteRegistry::load(registry_file),
+            ..Self::default()
+        }
+    }
+
+    /// 解析站点目录，只查询站点注册表，不注册新站点
+    ///
+    /// 参数：
+    /// - path: 站点目录路径
+    ///
+    /// 返回：
+    /// - 成功：(站点ID, 标准化后的路径)
+    /// - 失败：错误信息
+    fn resolve_site(&self, path: &str) -> AppResult<(String, PathBuf)> {
+        let path_buf = normalize_path(path)?;
+        Ok((self.registry.site_id(&path_buf), path_buf))
+    }
+
+    /// 注册站点目录，新注册的站点写入站点注册表
+    ///
+    /// 参数：
+    /// - path: 站点目录路径
+    ///
+    /// 返回：
+    /// - 成功：(站点ID, 标准化后的路径)
+    /// - 失败：错误信息
+    async fn register_site(&mut self, path: &str) -> AppResult<(String, PathBuf)> {
+        let path_buf = normalize_path(path)?;
+        if self.registry.register(&path_buf) {
+            self.save_registry().await;
+        }
+        Ok((self.registry.site_id(&path_buf), path_buf))
+    }
+
+    /// 持久化站点注册表
+    /// 保存失败不影响本次运行中的站点访问，仅记录日志
+    async fn save_registry(&self) {
+        if let Err(e) = self.registry.save().await {
+            log::warn!("站点注册表保存失败，重启后站点别名可能失效: {e}");
+        }
+    }
+
+    /// 当前监听信息，服务器未运行时为 None
+    fn listen_info(&self) -> Option<ListenInfo> {
+        self.server_handle
+            .as_ref()
+            .filter(|handle| !handle.join_handle.is_finished())
+            .map(|handle| *handle.listen_rx.borrow())
+    }
+
+    /// 当前监听地址，服务器未运行时为 None
+    fn server_address(&self) -> Option<SocketAddr> {
+        self.listen_info().map(|info| info.addr)
+    }
+
+    /// 停止服务器并等待其退出
+    async fn shutdown(&mut self) {
+        if let Some(handle) = self.server_handle.take() {
+            let _ = handle.shutdown_tx.send(());
+            handle.join_handle.await.ok();
+        }
+    }
+}
+
+/// 服务器运行状态
+/// 包含：
+/// - running: 服务器是否正在运行
+/// - url: 服务器访问地址
+/// - uptime: 本次监听已运行的秒数
+/// - sites: 已注册的静态站点
+/// - client_count: 已连接的WebSocket客户端数量
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerStatus {
+    running: bool,
+    url: Option<String>,
+    uptime: u64,
+    sites: Vec<SiteInfo>,
+    client_count: usize,
+}
+
+/// 静态站点信息
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SiteInfo {
+    hash: String,
+    path: PathBuf,
+    layers: Vec<PathBuf>,
+    options: SiteOptions,
 }
 
 impl Default for ServerState {
     fn default() -> Self {
-        // 创建广播通道（容量100条消息）
-        let (broadcast_tx, _) = broadcast::channel(100);
+        // 创建广播通道
+        let (broadcast_tx, _) = broadcast::channel(BROADCAST_CAPACITY);
 
         Self {
             app_state: Arc::new(AppState {
                 sites: RwLock::new(HashMap::new()),
                 broadcast_tx,
+                rooms: RwLock::new(HashMap::new()),
                 unicast_clients: Mutex::new(HashMap::new()),
+                access: RwLock::new(lan::AccessControl::default()),
+                watchers: Mutex::new(HashMap::new()),
+                overlays: RwLock::new(HashMap::new()),
+                diagnostics: Mutex::new(diagnostics::Diagnostics::default()),
+                access_log: Mutex::new(access_log::AccessLog::default()),
+                throttles: RwLock::new(HashMap::new()),
+                latest_scenes: Mutex::new(backpressure::LatestScenes::default()),
+                sessions: Mutex::new(recorder::Sessions::default()),
+                inspector: Mutex::new(inspector::Inspector::default()),
             }),
             server_handle: None,
-            server_address: None,
+            registry: SiteRegistry::default(),
         }
     }
 }
@@ -91,103 +497,313 @@ impl Default for ServerState {
 /// - ws: WebSocket升级请求
 /// - state: 应用程序状态
 /// - addr: 客户端地址
-/// - on_message: 消息处理通道
+/// - headers: 请求头，用于识别客户端所属站点和 User-Agent
+/// - query: 查询参数，可显式指定所属站点和上次的客户端ID
+/// - context: 消息、事件通道与心跳配置
+///
+/// 客户端所属站点依次从子域名、查询参数 `site` 和站点 Cookie 读取，
+/// 只接受已挂载的站点，无法识别时不加入任何站点房间。
+/// 查询参数 `client_id` 格式有效时沿用该ID，否则分配新的随机ID；ID 是否已被占用在注册客户端时检查。
+/// 带有查询参数 `bridge` 的连接为桥接脚本的上报连接，只转发上报消息
 async fn handle_ws(
     ws: WebSocketUpgrade,
     AxumState(state): AxumState<Arc<AppState>>,
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
-    on_message: Channel<String>,
-) -> impl IntoResponse {
-    ws.on_upgrade(move |socket| handle_ws_socket(socket, state, addr, on_message))
+    headers: HeaderMap,
+    Query(query): Query<WsQuery>,
+    context: WsContext,
+) -> Response {
+    // 重连时沿用上次的客户端ID，使编辑器中按ID保存的状态在刷新预览后仍然有效
+    let reused = query.client_id.filter(|id| is_client_id(id));
+    let Some(id) = reused.or_else(|| random_id(CLIENT_ID_BYTES).ok()) else {
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    };
+
+    // 优先根据子域名识别站点，其次是显式指定的站点和 Cookie
+    let site = match origin::site_from_host(&headers)
+        .map(str::to_owned)
+        .or(query.site)
+        .or_else(|| site_from_cookie(&headers))
+    {
+        Some(site) if state.sites.read().await.contains_key(&site) => Some(site),
+        _ => None,
+    };
+
+    let info = ClientInfo {
+        id,
+        addr,
+        site,
+        user_agent: headers
+            .get(USER_AGENT)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_owned),
+        connected_at: now_millis(),
+    };
+
+    let bridge = query.bridge.is_some();
+    ws.on_upgrade(move |socket| handle_ws_socket(socket, state, info, bridge, context))
+}
+
+/// 是否为 random_id 生成的客户端ID（CLIENT_ID_BYTES 字节的小写十六进制）
+fn is_client_id(id: &str) -> bool {
+    id.len() == CLIENT_ID_BYTES * 2
+        && id
+            .bytes()
+            .all(|byte| byte.is_ascii_digit() || (b'a'..=b'f').contains(&byte))
+}
+
+/// 从 Cookie 中读取 WebSocket 客户端所属的站点哈希
+fn site_from_cookie(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get_all(COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(';'))
+        .filter_map(|pair| pair.trim().split_once('='))
+        .find(|(name, _)| *name == SITE_COOKIE_NAME)
+        .map(|(_, hash)| hash.to_string())
 }
 
 /// WebSocket连接建立后的处理函数
 /// 实现功能：
-/// 1. 消息接收和广播：处理客户端消息并转发给其他客户端
-/// 2. 客户端注册和注销：管理客户端连接状态
-/// 3. 错误处理和连接关闭：确保资源正确释放
+/// 1. 消息接收和转发：校验客户端发来的调试消息和二进制帧，并转发给编辑器；无法解析的消息附带原因转发
+/// 2. 客户端注册和注销：管理客户端连接状态，并通知编辑器客户端连接与断开
+/// 3. 心跳检测：定时发送 Ping，超时未收到客户端任何消息时断开连接
+/// 4. 错误处理和连接关闭：任一方向结束时关闭整个连接，确保资源正确释放
 ///
 /// 参数：
 /// - socket: WebSocket连接实例
 /// - state: 应用程序状态
-/// - addr: 客户端地址
-/// - on_message: 消息处理通道
+/// - info: 客户端身份信息
+/// - bridge: 是否为桥接脚本的上报连接，上报连接不加入客户端列表和站点房间，不接收广播，也不记录运行时状态
+/// - context: 消息、事件通道与心跳配置
 async fn handle_ws_socket(
     socket: WebSocket,
     state: Arc<AppState>,
-    addr: SocketAddr,
-    on_message: Channel<String>,
+    info: ClientInfo,
+    bridge: bool,
+    context: WsContext,
 ) {
+    let WsContext {
+        on_message,
+        on_event,
+        heartbeat,
+    } = context;
     let (mut ws_tx, mut ws_rx) = socket.split();
 
-    // 创建单播通道
-    let (unicast_tx, mut unicast_rx) = mpsc::unbounded_channel();
+    // 检查客户端ID是否被占用与注册客户端在同一次加锁中完成，避免两个连接同时沿用同一ID
+    let mut info = info;
+    let clients = match bridge {
+        true => None,
+        false => Some(state.unicast_clients.lock().await),
+    };
+    if clients
+        .as_ref()
+        .is_some_and(|clients| clients.contains_key(&info.id))
+    {
+        match random_id(CLIENT_ID_BYTES) {
+            Ok(id) => info.id = id,
+            Err(e) => {
+                log::warn!("无法为客户端分配新的ID: {e}");
+                return;
+            }
+        }
+    }
+    let client_id = info.id.clone();
+    let liveness = Arc::new(Liveness::new());
+
+    // 创建有界单播通道
+    let (unicast_tx, mut unicast_rx) = mpsc::channel(backpressure::UNICAST_CAPACITY);
+    let stats = Arc::new(backpressure::ClientStats::default());
+    let client = UnicastClient {
+        info: info.clone(),
+        tx: unicast_tx,
+        stats: stats.clone(),
+    };
 
-    // 注册客户端
-    state.unicast_clients.lock().await.insert(addr, unicast_tx);
+    let mut broadcast_rx = None;
+    let mut room_rx = None;
+    if let Some(mut clients) = clients {
+        // 握手：告知客户端其ID
+        if let Ok(handshake) = serde_json::to_string(&HandshakeMessage::Connected {
+            client_id: &client_id,
+        }) {
+            let handshake = Message::Text(handshake.into());
+            state.sessions.lock().await.record(
+                Direction::FromServer,
+                Target::Client(client_id.clone()),
+                &handshake,
+            );
+            let _ = client.try_send(handshake);
+        }
 
-    // 订阅广播
-    let mut broadcast_rx = state.broadcast_tx.subscribe();
+        // 注册客户端
+        clients.insert(client_id.clone(), client);
+        drop(clients);
+        let _ = on_event.send(ServerEvent::ClientConnected {
+            client: info.clone(),
+        });
 
-    // 消息处理任务
-    let recv_task = tokio::spawn({
-        let on_message = on_message.clone();
+        // 订阅广播，并加入所属站点的房间
+        broadcast_rx = Some(state.broadcast_tx.subscribe());
+        if let Some(site) = &info.site {
+            room_rx = Some(state.join_room(site).await.subscribe());
+        }
+    }
+
+    // 消息处理任务，收到任何消息（包括 Pong）都视为客户端仍然活跃
+    let mut recv_task = tokio::spawn({
+        let state = state.clone();
+        let info = info.clone();
+        let liveness = liveness.clone();
         async move {
-            while let Some(Ok(msg)) = ws_rx.next().await {
-                match msg {
+            while let Some(msg) = ws_rx.next().await {
+                let Ok(msg) = msg else {
+                    return DisconnectReason::Error;
+                };
+                liveness.touch();
+                state.sessions.lock().await.record(
+                    Direction::FromGame,
+                    Target::Client(info.id.clone()),
+                    &msg,
+                );
+
+                let payload = match msg {
                     Message::Text(text) => {
-                        let _ = on_message.send(text.to_string());
+                        ClientPayload::from_text(&text).map_err(|e| (Some(text.to_string()), e))
                     }
+                    Message::Binary(bytes) => BinaryFrame::parse(&bytes)
+                        .map(ClientPayload::Binary)
+                        .map_err(|e| (None, e)),
                     Message::Close(_) => {
                         break;
                     }
                     _ => {
                         continue;
                     }
+                };
+
+                // 无法解析的消息同样转发给编辑器，便于排查协议不匹配的问题
+                let message = payload.unwrap_or_else(|(raw, e)| {
+                    log::warn!("客户端 {} 的消息无法解析: {e}", info.id);
+                    ClientPayload::Invalid {
+                        raw,
+                        error: e.to_string(),
+                    }
+                });
+                // 桥接脚本的上报连接不是游戏客户端，不记录运行时状态
+                if !bridge {
+                    state.inspector.lock().await.observe(&info.id, &message);
                 }
+                let _ = on_message.send(ClientMessage {
+                    client: info.clone(),
+                    message,
+                });
             }
+            DisconnectReason::Closed
         }
     });
 
-    // 发送任务（综合广播和单播）
-    let send_task = tokio::spawn(async move {
-        loop {
-            tokio::select! {
-                // 处理广播消息
-                Ok(msg) = broadcast_rx.recv() => {
-                    if ws_tx.send(msg).await.is_err() {
-                        break;
-                    }
-                }
-                // 处理单播消息
-                Some(msg) = unicast_rx.recv() => {
-                    if ws_tx.send(msg).await.is_err() {
-                        break;
+    // 发送任务（综合广播、房间广播、单播和心跳）
+    // 客户端处理过慢时广播通道会跳过旧消息，此时记录落后数量并通知编辑器重新同步
+    let mut send_task = tokio::spawn({
+        let client_id = client_id.clone();
+        let on_event = on_event.clone();
+        async move {
+            let mut ping_timer = tokio::time::interval(heartbeat.interval());
+            ping_timer.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+            let report_lag = |skipped: u64| {
+                stats.record_lag(skipped);
+                let _ = on_event.send(ServerEvent::ClientLagged {
+                    client_id: client_id.clone(),
+                    skipped,
+                });
+            };
+
+            loop {
+                let msg = tokio::select! {
+                    // 处理广播消息
+                    result = recv_subscribed(&mut broadcast_rx) => match result {
+                        Ok(msg) => msg,
+                        Err(RecvError::Lagged(skipped)) => {
+                            report_lag(skipped);
+                            continue;
+                        }
+                        Err(RecvError::Closed) => break DisconnectReason::Closed,
+                    },
+                    // 处理房间广播消息，站点卸载后房间关闭，不再接收
+                    result = recv_subscribed(&mut room_rx) => match result {
+                        Ok(msg) => msg,
+                        Err(RecvError::Lagged(skipped)) => {
+                            report_lag(skipped);
+                            continue;
+                        }
+                        Err(RecvError::Closed) => {
+                            room_rx = None;
+                            continue;
+                        }
+                    },
+                    // 处理单播消息
+                    Some(msg) = unicast_rx.recv() => msg,
+                    // 发送心跳，超时未响应时断开
+                    _ = ping_timer.tick() => {
+                        if liveness.is_expired(&heartbeat) {
+                            break DisconnectReason::Timeout;
+                        }
+                        Message::Ping(Default::default())
                     }
+                };
+
+                if ws_tx.send(msg).await.is_err() {
+                    break DisconnectReason::Error;
                 }
-                // 退出条件
-                else => break,
             }
         }
     });
 
-    // 等待任务完成
-    tokio::select! {
-        _ = recv_task => (),
-        _ = send_task => (),
+    // 任一任务结束即关闭连接
+    let reason = tokio::select! {
+        reason = &mut recv_task => reason,
+        reason = &mut send_task => reason,
     }
+    .unwrap_or(DisconnectReason::Error);
+    recv_task.abort();
+    send_task.abort();
 
     // 注销客户端
-    state.unicast_clients.lock().await.remove(&addr);
-    println!("Client disconnected: {}", addr);
+    if !bridge {
+        state.inspector.lock().await.remove_client(&client_id);
+        state.unicast_clients.lock().await.remove(&client_id);
+        let _ = on_event.send(ServerEvent::ClientDisconnected {
+            client: info,
+            reason,
+        });
+    }
+}
+
+/// 接收广播或站点房间消息，未订阅时永远等待
+async fn recv_subscribed(
+    room_rx: &mut Option<broadcast::Receiver<Message>>,
+) -> Result<Message, RecvError> {
+    match room_rx {
+        Some(rx) => rx.recv().await,
+        None => std::future::pending().await,
+    }
 }
 
 /// 处理静态文件请求
 /// 根据站点哈希和请求路径返回对应的静态文件
+/// 文件存在于内存覆盖层时返回覆盖层中的内容，否则依次在站点目录和附加目录中查找
+/// 站点开启编辑器桥接或存档同步时，向首页注入对应的脚本
+/// 站点设置了网络限速配置时，按配置延迟、随机失败或限制带宽
+/// 按站点选项压缩响应并添加响应头
+/// 返回站点首页时写入站点 Cookie，使随后建立的同源 WebSocket 连接能识别所属站点
 ///
 /// 参数：
 /// - state: 应用程序状态
 /// - hash: 站点哈希值
+/// - headers: 请求头，用于内容协商、条件请求和范围请求
 /// - path: 请求的文件路径
 ///
 /// 返回：
@@ -196,11 +812,38 @@ async fn handle_ws_socket(
 async fn handle_static_request(
     AxumState(state): AxumState<Arc<AppState>>,
     AxumPath(hash): AxumPath<String>,
+    headers: HeaderMap,
     path: Option<String>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    let sites = state.sites.read().await;
+    let throttle = state.throttles.read().await.get(&hash).copied();
+    if let Some(throttle) = &throttle {
+        throttle.before_request().await?;
+    }
+
+    let site = state
+        .sites
+        .read()
+        .await
+        .get(&hash)
+        .map(|site| (site.serve_dir.clone(), site.options));
+
+    if let Some((serve_dir, options)) = site {
+        let is_document = path
+            .as_deref()
+            .is_none_or(|p| p.is_empty() || p == "index.html");
+
+        // 优先提供内存覆盖层中的文件
+        let mut overlay = match overlay::overlay_key(path.as_deref().unwrap_or_default()) {
+            Some(key) => state
+                .overlays
+                .read()
+                .await
+                .get(&hash)
+                .and_then(|files| files.get(&key))
+                .map(|content| overlay::overlay_response(&key, content.clone())),
+            None => None,
+        };
 
-    if let Some((_, serve_dir)) = sites.get(&hash) {
         let uri = match path {
             Some(p) => {
                 // 对路径进行 URL 编码
@@ -214,12 +857,57 @@ async fn handle_static_request(
             None => "/".to_string(),
         };
 
-        match serve_dir
-            .to_owned()
-            .oneshot(Request::builder().uri(uri).body(()).unwrap())
+        let mut request = Request::builder().uri(uri).body(()).unwrap();
+        *request.headers_mut() = headers;
+
+        // 开启编辑器桥接或存档同步时改写首页，注入对应的脚本
+        let scripts = is_document
+            .then(|| bridge::document_scripts(&hash, &options))
+            .flatten();
+        if scripts.is_some() {
+            bridge::strip_conditional_headers(request.headers_mut());
+        }
+
+        let serve = tower::service_fn(move |request: Request<()>| {
+            let overlay = overlay.take();
+            let serve_dir = serve_dir.clone();
+            let scripts = scripts.clone();
+            async move {
+                let response = match overlay {
+                    Some(response) => response,
+                    None => serve_dir.serve(request).await,
+                };
+                Ok::<_, Infallible>(match scripts {
+                    Some(scripts) => bridge::inject_scripts(response, &scripts).await,
+                    None => response,
+                })
+            }
+        });
+
+        // 按站点选项压缩响应，编码方式根据 Accept-Encoding 协商
+        let response = Compression::new(serve)
+            .gzip(options.gzip)
+            .br(options.brotli)
+            .oneshot(request)
             .await
-        {
-            Ok(response) => Ok(response.into_response()),
+            .map(IntoResponse::into_response);
+
+        match response {
+            Ok(mut response) => {
+                options.apply_headers(&mut response);
+
+                if is_document && response.status().is_success() {
+                    let cookie =
+                        format!("{SITE_COOKIE_NAME}={hash}; Path=/api/webgalsync; SameSite=Lax");
+                    if let Ok(value) = HeaderValue::from_str(&cookie) {
+                        response.headers_mut().append(SET_COOKIE, value);
+                    }
+                }
+                Ok(match &throttle {
+                    Some(throttle) => throttle.limit_response(response),
+                    None => response,
+                })
+            }
             Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
         }
     } else {
@@ -227,18 +915,130 @@ async fn handle_static_request(
     }
 }
 
+/// 处理静态站点的 CORS 预检请求
+/// 站点开启跨域访问时返回允许的方法与请求头，未开启时返回 405
+///
+/// 参数：
+/// - state: 应用程序状态
+/// - hash: 站点ID
+/// - headers: 预检请求的请求头
+///
+/// 返回：预检响应，站点不存在时返回 404
+async fn handle_preflight(
+    AxumState(state): AxumState<Arc<AppState>>,
+    AxumPath(hash): AxumPath<String>,
+    headers: HeaderMap,
+) -> Response {
+    let options = state.sites.read().await.get(&hash).map(|site| site.options);
+    match options {
+        Some(options) => options
+            .preflight_response(&headers)
+            .unwrap_or_else(|| StatusCode::METHOD_NOT_ALLOWED.into_response()),
+        None => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+/// 构建服务器路由
+/// 路径路由外层包裹子域名路由，使 `{站点ID}.localhost` 的请求在路由匹配前完成改写
+///
+/// 参数：
+/// - app_state: 应用程序状态
+/// - on_message: 消息处理通道
+/// - on_event: 服务器事件通道
+/// - heartbeat: WebSocket 心跳配置
+fn build_router(
+    app_state: Arc<AppState>,
+    on_message: Channel<ClientMessage>,
+    on_event: Channel<ServerEvent>,
+    heartbeat: HeartbeatOptions,
+) -> Router {
+    let context = WsContext {
+        on_message,
+        on_event,
+        heartbeat,
+    };
+    let routes = Router::new()
+        .route(
+            "/api/webgalsync",
+            get(move |ws, state, addr, headers, query| {
+                handle_ws(ws, state, addr, headers, query, context)
+            }),
+        )
+        .route("/api/saves/{hash}", get(saves::handle_get_saves))
+        .route(
+            "/api/saves/{hash}/{key}",
+            put(saves::handle_put_save)
+                .delete(saves::handle_delete_save)
+                .layer(DefaultBodyLimit::max(saves::MAX_SAVE_SIZE)),
+        )
+        .route("/game/{hash}", get(handle_redirect))
+        .route(
+            "/game/{hash}/",
+            get(
+                |state: AxumState<Arc<AppState>>, hash: AxumPath<String>, headers: HeaderMap| {
+                    handle_static_request(state, hash, headers, None)
+                },
+            )
+            .options(handle_preflight),
+        )
+        .route(
+            "/game/{hash}/{*path}",
+            get(
+                |state: AxumState<Arc<AppState>>,
+                 AxumPath((hash, path)): AxumPath<(String, String)>,
+                 headers: HeaderMap| {
+                    handle_static_request(state, AxumPath(hash), headers, Some(path))
+                },
+            )
+            .options(
+                |state: AxumState<Arc<AppState>>,
+                 AxumPath((hash, _)): AxumPath<(String, String)>,
+                 headers: HeaderMap| {
+                    handle_preflight(state, AxumPath(hash), headers)
+                },
+            ),
+        )
+        .route_layer(middleware::from_fn_with_state(
+            app_state.clone(),
+            lan::require_token,
+        ))
+        .route_layer(middleware::from_fn_with_state(
+            app_state.clone(),
+            diagnostics::record_errors,
+        ))
+        .layer(middleware::from_fn_with_state(
+            app_state.clone(),
+            access_log::log_requests,
+        ))
+        .with_state(app_state);
+
+    Router::new()
+        .fallback_service(routes)
+        .layer(middleware::from_fn(origin::route_by_host))
+        // 站点响应按站点选项设置缓存策略，其余响应统一禁止缓存
+        .layer(SetResponseHeaderLayer::if_not_present(
+            CACHE_CONTROL,
+            HeaderValue::from_static(NO_STORE),
+        ))
+}
+
 /// 启动HTTP服务器
 /// 功能：
 /// 1. 停止已存在的服务器实例
-/// 2. 绑定指定端口（如果端口被占用则自动选择新端口）
+/// 2. 绑定指定端口（如果端口被占用则自动选择新端口，并发送 PortChanged 事件）
 /// 3. 设置路由和中间件
 /// 4. 启动服务器并返回访问地址
 ///
+/// 绑定到非回环地址时启用局域网预览模式，局域网设备需携带预览会话令牌才能访问
+///
 /// 参数：
 /// - state: 服务器状态
 /// - host: 服务器主机地址
 /// - port: 服务器端口号
+/// - auto_restart: 意外退出后是否自动重启，默认不重启
+/// - heartbeat: WebSocket 心跳配置，默认每 15 秒 Ping 一次，45 秒未响应时断开
 /// - on_message: 消息处理通道
+/// - on_event: 服务器生命周期与客户端连接事件通道
 ///
 /// 返回：
 /// - 成功：服务器访问地址
@@ -248,87 +1048,137 @@ pub async fn start_server(
     state: TauriState<'_, Mutex<ServerState>>,
     host: String,
     port: u16,
-    on_message: Channel<String>,
+    auto_restart: Option<bool>,
+    heartbeat: Option<HeartbeatOptions>,
+    on_message: Channel<ClientMessage>,
+    on_event: Channel<ServerEvent>,
 ) -> AppResult<String> {
+    let heartbeat = heartbeat.unwrap_or_default();
+    heartbeat.validate()?;
+
     let mut state_guard = state.lock().await;
 
     // 停止已存在的服务器
-    if let Some(handle) = state_guard.server_handle.take() {
-        let _ = handle.shutdown_tx.send(());
-        handle.join_handle.await.ok();
-    }
+    state_guard.shutdown().await;
 
     // 获取可用端口
-    let address = format!("{host}:{port}");
-    let listener = match TcpListener::bind(&address).await {
-        Ok(listener) => listener,
-        Err(_) => {
-            let new_port =
-                pick_unused_port().ok_or_else(|| AppError::Server("无法找到可用端口".into()))?;
-            TcpListener::bind(format!("{host}:{new_port}")).await?
-        }
-    };
-
+    let listener = supervisor::bind_listener(&host, port).await?;
     let addr = listener.local_addr()?;
+    let url = format!("http://{addr}");
 
-    // 构建路由
-    let app = Router::new()
-        .route(
-            "/api/webgalsync",
-            get(move |ws, state, addr| handle_ws(ws, state, addr, on_message)),
-        )
-        .route("/game/{hash}", get(handle_redirect))
-        .route(
-            "/game/{hash}/",
-            get(
-                |state: AxumState<Arc<AppState>>, hash: AxumPath<String>| async move {
-                    handle_static_request(state, hash, None).await
-                },
-            ),
-        )
-        .route(
-            "/game/{hash}/{*path}",
-            get(
-                |state: AxumState<Arc<AppState>>, path: AxumPath<(String, String)>| async move {
-                    let (hash, path) = path.0;
-                    handle_static_request(state, AxumPath(hash), Some(path)).await
-                },
-            ),
-        )
-        .with_state(state_guard.app_state.clone())
-        // 统一添加禁止缓存的响应头
-        .layer(SetResponseHeaderLayer::overriding(
-            CACHE_CONTROL,
-            HeaderValue::from_static("no-store, no-cache, must-revalidate, max-age=0"),
-        ));
+    if addr.port() != port {
+        let _ = on_event.send(ServerEvent::PortChanged {
+            requested: port,
+            actual: addr.port(),
+            url: url.clone(),
+        });
+    }
 
-    let (shutdown_tx, shutdown_rx) = oneshot::channel();
+    // 绑定到非回环地址时要求局域网请求携带访问令牌
+    state_guard.app_state.access.write().await.enabled = !addr.ip().is_loopback();
 
-    let join_handle = tokio::spawn(async move {
-        axum::serve(
-            listener,
-            app.into_make_service_with_connect_info::<SocketAddr>(),
-        )
-        .with_graceful_shutdown(async {
-            shutdown_rx.await.ok();
-        })
-        .await
-        .unwrap();
+    let router = build_router(
+        state_guard.app_state.clone(),
+        on_message,
+        on_event.clone(),
+        heartbeat,
+    );
+
+    let (shutdown_tx, shutdown_rx) = oneshot::channel();
+    let (listen_tx, listen_rx) = watch::channel(ListenInfo {
+        addr,
+        started_at: Instant::now(),
     });
 
+    let options = SupervisorOptions {
+        host,
+        port,
+        auto_restart: auto_restart.unwrap_or(false),
+    };
+
+    let join_handle = tokio::spawn(supervisor::supervise(
+        listener,
+        router,
+        options,
+        shutdown_rx,
+        listen_tx,
+        on_event,
+    ));
+
     state_guard.server_handle = Some(ServerHandle {
         join_handle,
         shutdown_tx,
+        listen_rx,
     });
 
-    state_guard.server_address = Some(addr);
+    log::info!("预览服务器已启动: {addr}");
+    Ok(url)
+}
 
-    println!("Server started at: {}", addr);
-    Ok(format!("http://{addr}"))
+/// 停止HTTP服务器
+/// 优雅关闭正在运行的服务器，服务器未运行时不做任何操作
+///
+/// 参数：
+/// - state: 服务器状态
+///
+/// 返回：
+/// - 成功：空值
+/// - 失败：错误信息
+#[tauri::command]
+pub async fn stop_server(state: TauriState<'_, Mutex<ServerState>>) -> AppResult<()> {
+    let mut state_guard = state.lock().await;
+
+    state_guard.shutdown().await;
+    state_guard.app_state.access.write().await.enabled = false;
+
+    log::info!("预览服务器已停止");
+    Ok(())
+}
+
+/// 获取服务器运行状态
+/// 返回访问地址、运行时长、已注册站点和已连接客户端数量
+///
+/// 参数：
+/// - state: 服务器状态
+///
+/// 返回：
+/// - 成功：服务器运行状态
+/// - 失败：错误信息
+#[tauri::command]
+pub async fn get_server_status(
+    state: TauriState<'_, Mutex<ServerState>>,
+) -> AppResult<ServerStatus> {
+    let state_guard = state.lock().await;
+    let listen_info = state_guard.listen_info();
+
+    let mut sites: Vec<_> = state_guard
+        .app_state
+        .sites
+        .read()
+        .await
+        .iter()
+        .map(|(hash, site)| SiteInfo {
+            hash: hash.clone(),
+            path: site.path.clone(),
+            layers: site.layers.clone(),
+            options: site.options,
+        })
+        .collect();
+    sites.sort_by(|a, b| a.path.cmp(&b.path));
+
+    let client_count = state_guard.app_state.unicast_clients.lock().await.len();
+
+    Ok(ServerStatus {
+        running: listen_info.is_some(),
+        url: listen_info.map(|info| format!("http://{}", info.addr)),
+        uptime: listen_info.map_or(0, |info| info.started_at.elapsed().as_secs()),
+        sites,
+        client_count,
+    })
 }
 
 /// 将不带斜杠的URL重定向到带斜杠的URL
-/// 例如：/game/abc -> /game/abc/
+/// 例如：/game/abc -> /game/abc/，查询参数（如访问令牌）保持不变
 ///
 /// 参数：
 /// - uri: 原始请求URI
@@ -336,19 +1186,40 @@ pub async fn start_server(
 /// 返回：
 /// - 重定向响应
 async fn handle_redirect(uri: Uri) -> Result<Redirect, StatusCode> {
-    Ok(Redirect::permanent(&format!("{}/", uri.path())))
+    let target = match uri.query() {
+        Some(query) => format!("{}/?{query}", uri.path()),
+        None => format!("{}/", uri.path()),
+    };
+    Ok(Redirect::permanent(&target))
+}
+
+/// 生成十六进制随机ID
+///
+/// 参数：
+/// - len: 随机字节数
+fn random_id(len: usize) -> AppResult<String> {
+    let mut bytes = vec![0u8; len];
+    getrandom::fill(&mut bytes).map_err(|e| AppError::Server(format!("无法生成随机ID: {e}")))?;
+    Ok(bytes.iter().map(|byte| format!("{byte:02x}")).collect())
+}
+
+/// 当前时间（Unix 毫秒）
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as u64)
+        .unwrap_or_default()
 }
 
-/// 标准化路径并生成唯一哈希值
-/// 用于标识和管理静态站点
+/// 校验并标准化站点目录路径
 ///
 /// 参数：
 /// - path: 待处理的路径字符串
 ///
 /// 返回：
-/// - 成功：(站点哈希值, 标准化后的路径)
+/// - 成功：标准化后的路径
 /// - 失败：错误信息
-fn normalize_and_hash_path(path: &str) -> AppResult<(String, PathBuf)> {
+fn normalize_path(path: &str) -> AppResult<PathBuf> {
     let path_buf = PathBuf::from(path);
 
     if !path_buf.exists() {
@@ -359,52 +1230,59 @@ fn normalize_and_hash_path(path: &str) -> AppResult<(String, PathBuf)> {
         return Err(AppError::Server("路径必须是目录".into()));
     }
 
-    let canonical_path = path_buf
+    path_buf
         .canonicalize()
-        .map_err(|e| AppError::Server(format!("无法标准化路径: {e}")))?;
-
-    let mut hasher = std::collections::hash_map::DefaultHasher::new();
-    canonical_path.hash(&mut hasher);
-    let hash = format!("{:x}", hasher.finish());
-
-    Ok((hash, canonical_path))
+        .map_err(|e| AppError::Server(format!("无法标准化路径: {e}")))
 }
 
 /// 添加静态站点
-/// 将指定目录注册为静态站点，并返回站点哈希值
+/// 将指定目录注册为静态站点，并返回站点ID
+/// 同时监听该目录，文件变化时通知正在预览该站点的客户端
+///
+/// 站点ID由站点注册表分配：默认为标准化路径的稳定哈希，设置别名后为别名。
+/// 同一目录的站点ID在应用重启后保持不变，预览地址和 WebGAL 存档因此不会失效。
 ///
 /// 参数：
 /// - state: 服务器状态
 /// - path: 静态站点目录路径
+/// - alias: 站点别名，为 None 时保留已有别名
+/// - options: 站点选项（压缩、响应头、缓存模式），为 None 时保留已有选项
+/// - layers: 在站点目录之后依次查找的附加目录，为 None 时保留已有附加目录。
+///   例如传入引擎目录，游戏目录中不存在的文件从引擎目录提供，游戏无需复制引擎
 ///
 /// 返回：
-/// - 成功：站点哈希值
+/// - 成功：站点ID
 /// - 失败：错误信息
 #[tauri::command]
 pub async fn add_static_site(
     state: TauriState<'_, Mutex<ServerState>>,
     path: String,
+    alias: Option<String>,
+    options: Option<SiteOptions>,
+    layers: Option<Vec<String>>,
 ) -> AppResult<String> {
-    let state_guard = state.lock().await;
-    let (hash, path_buf) = normalize_and_hash_path(&path)?;
-
-    let mut sites = state_guard.app_state.sites.write().await;
+    let layers = layers
+        .map(|layers| layers::normalize_layers(&layers))
+        .transpose()?;
 
-    // 如果站点已存在，直接返回其哈希值
-    if sites.contains_key(&hash) {
-        return Ok(hash);
-    }
+    let mut state_guard = state.lock().await;
 
-    // 创建服务目录实例
-    let serve_dir = ServeDir::new(&path_buf).append_index_html_on_directories(true);
+    let (hash, path_buf) = match alias {
+        Some(alias) => set_alias(&mut state_guard, &path, Some(alias)).await?,
+        None => state_guard.register_site(&path).await?,
+    };
 
-    sites.insert(hash.clone(), (path_buf, serve_dir));
+    state_guard
+        .app_state
+        .mount_site(&hash, path_buf, options, layers)
+        .await;
 
     Ok(hash)
 }
 
 /// 移除静态站点
-/// 根据路径移除已注册的静态站点
+/// 根据路径移除已注册的静态站点，停止文件监听并撤销该站点的预览会话
+/// 站点注册表中的记录会保留，再次添加时仍使用相同的站点ID
 ///
 /// 参数：
 /// - state: 服务器状态
@@ -419,20 +1297,104 @@ pub async fn remove_static_site(
     path: String,
 ) -> AppResult<()> {
     let state_guard = state.lock().await;
-    let (hash, _) = normalize_and_hash_path(&path)?;
+    let (hash, _) = state_guard.resolve_site(&path)?;
 
-    let mut sites = state_guard.app_state.sites.write().await;
-    sites.remove(&hash);
+    state_guard.app_state.unmount_site(&hash).await;
 
     Ok(())
 }
 
+/// 设置站点别名
+/// 站点已挂载时，以新的站点ID重新挂载，并沿用原有的网络限速配置
+///
+/// 返回：
+/// - 成功：(新的站点ID, 标准化后的路径)
+/// - 失败：错误信息
+async fn set_alias(
+    state: &mut ServerState,
+    path: &str,
+    alias: Option<String>,
+) -> AppResult<(String, PathBuf)> {
+    let (old_hash, path_buf) = state.resolve_site(path)?;
+    let hash = state.registry.set_alias(&path_buf, alias)?.id().to_string();
+    state.save_registry().await;
+
+    if hash != old_hash {
+        let throttle = state
+            .app_state
+            .throttles
+            .read()
+            .await
+            .get(&old_hash)
+            .copied();
+        if let Some(site) = state.app_state.unmount_site(&old_hash).await {
+            state
+                .app_state
+                .mount_site(
+                    &hash,
+                    path_buf.clone(),
+                    Some(site.options),
+                    Some(site.layers),
+                )
+                .await;
+            if let Some(throttle) = throttle {
+                state
+                    .app_state
+                    .throttles
+                    .write()
+                    .await
+                    .insert(hash.clone(), throttle);
+            }
+        }
+    }
+
+    Ok((hash, path_buf))
+}
+
+/// 设置站点别名
+/// 别名只能包含小写字母、数字和连字符，设置后作为站点ID出现在预览地址中
+///
+/// 参数：
+/// - state: 服务器状态
+/// - path: 静态站点目录路径
+/// - alias: 新别名，为 None 时清除别名并恢复使用哈希
+///
+/// 返回：
+/// - 成功：新的站点ID
+/// - 失败：错误信息
+#[tauri::command]
+pub async fn set_site_alias(
+    state: TauriState<'_, Mutex<ServerState>>,
+    path: String,
+    alias: Option<String>,
+) -> AppResult<String> {
+    let mut state_guard = state.lock().await;
+    let (hash, _) = set_alias(&mut state_guard, &path, alias).await?;
+    Ok(hash)
+}
+
+/// 获取站点注册表
+/// 返回添加过的站点目录及其站点ID信息，目录已不存在或超出数量上限的旧条目会被清理
+///
+/// 参数：
+/// - state: 服务器状态
+///
+/// 返回：
+/// - 成功：注册表条目列表
+/// - 失败：错误信息
+#[tauri::command]
+pub async fn list_sites(state: TauriState<'_, Mutex<ServerState>>) -> AppResult<Vec<SiteEntry>> {
+    let state_guard = state.lock().await;
+    Ok(state_guard.registry.entries().to_vec())
+}
+
 /// 广播消息
 /// 向所有连接的WebSocket客户端发送消息
+/// 场景跳转会被记录，用于重新同步落后的客户端
 ///
 /// 参数：
 /// - state: 服务器状态
-/// - message: 要广播的消息内容
+/// - message: 要广播的调试消息，反序列化时按 WebGAL 调试协议校验
 ///
 /// 返回：
 /// - 成功：空值
@@ -440,67 +1402,121 @@ pub async fn remove_static_site(
 #[tauri::command]
 pub async fn broadcast_message(
     state: TauriState<'_, Mutex<ServerState>>,
-    message: String,
+    message: DebugMessage,
 ) -> AppResult<()> {
     let state_guard = state.lock().await;
+    let json = message.to_json()?;
+    if message.is_scene_state() {
+        state_guard
+            .app_state
+            .latest_scenes
+            .lock()
+            .await
+            .record(None, json.clone());
+    }
+
+    let message = Message::Text(json.into());
+    state_guard.app_state.sessions.lock().await.record(
+        Direction::ToGame,
+        Target::Broadcast,
+        &message,
+    );
+
     // 使用广播通道高效发送
     state_guard
         .app_state
         .broadcast_tx
-        .send(Message::Text(message.into()))
+        .send(message)
         .map_err(|_| AppError::Server("Broadcast failed".into()))?;
     Ok(())
 }
 
-/// 单播消息
-/// 向指定的WebSocket客户端发送消息
+/// 站点广播消息
+/// 仅向正在预览指定站点的WebSocket客户端发送消息
+/// 场景跳转会被记录，用于重新同步落后的客户端
 ///
 /// 参数：
 /// - state: 服务器状态
-/// - client_addr: 目标客户端地址（格式：IP:端口）
-/// - message: 要发送的消息内容
+/// - path: 静态站点目录路径
+/// - message: 要广播的调试消息，反序列化时按 WebGAL 调试协议校验
 ///
 /// 返回：
 /// - 成功：空值
 /// - 失败：错误信息
 #[tauri::command]
-pub async fn unicast_message(
+pub async fn broadcast_to_site(
     state: TauriState<'_, Mutex<ServerState>>,
-    client_addr: String,
-    message: String,
+    path: String,
+    message: DebugMessage,
 ) -> AppResult<()> {
     let state_guard = state.lock().await;
-    let clients = state_guard.app_state.unicast_clients.lock().await;
-
-    // 解析地址
-    let addr: SocketAddr = client_addr
-        .parse()
-        .map_err(|_| AppError::Server("Invalid client address".into()))?;
+    let (hash, _) = state_guard.resolve_site(&path)?;
 
-    if let Some(tx) = clients.get(&addr) {
-        tx.send(Message::Text(message.into()))
-            .map_err(|_| AppError::Server("Failed to send unicast message".into()))?;
-        Ok(())
-    } else {
-        Err(AppError::Server("Client not found".into()))
+    let json = message.to_json()?;
+    if message.is_scene_state() {
+        state_guard
+            .app_state
+            .latest_scenes
+            .lock()
+            .await
+            .record(Some(&hash), json.clone());
     }
+
+    let message = Message::Text(json.into());
+    state_guard.app_state.sessions.lock().await.record(
+        Direction::ToGame,
+        Target::Site(hash.clone()),
+        &message,
+    );
+
+    state_guard
+        .app_state
+        .broadcast_to_room(&hash, message)
+        .await
+}
+
+/// 单播消息
+/// 向指定的WebSocket客户端发送消息，客户端的消息队列已满时丢弃该消息并返回错误
+///
+/// 参数：
+/// - state: 服务器状态
+/// - client_id: 目标客户端ID
+/// - message: 要发送的调试消息，反序列化时按 WebGAL 调试协议校验
+///
+/// 返回：
+/// - 成功：空值
+/// - 失败：错误信息
+#[tauri::command]
+pub async fn unicast_message(
+    state: TauriState<'_, Mutex<ServerState>>,
+    client_id: String,
+    message: DebugMessage,
+) -> AppResult<()> {
+    let state_guard = state.lock().await;
+    state_guard
+        .app_state
+        .send_to_client(&client_id, &message)
+        .await
 }
 
 /// 获取已连接的客户端列表
-/// 返回所有连接的WebSocket客户端的地址列表
+/// 返回所有连接的WebSocket客户端的身份信息，按连接时间排序
 ///
 /// 参数：
 /// - state: 服务器状态
 ///
 /// 返回：
-/// - 成功：客户端地址列表
+/// - 成功：客户端信息列表
 /// - 失败：错误信息
 #[tauri::command]
 pub async fn get_connected_clients(
     state: TauriState<'_, Mutex<ServerState>>,
-) -> AppResult<Vec<String>> {
+) -> AppResult<Vec<ClientInfo>> {
     let state_guard = state.lock().await;
     let clients = state_guard.app_state.unicast_clients.lock().await;
 
-    Ok(clients.keys().map(|addr| addr.to_string()).collect())
+    let mut infos: Vec<_> = clients.values().map(|client| client.info.clone()).collect();
+    infos.sort_by_key(|info| info.connected_at);
+
+    Ok(infos)
 }
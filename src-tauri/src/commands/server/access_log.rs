@@ -0,0 +1,286 @@
+// 访问日志模块：记录预览服务器的每个请求，用于分析资源加载情况
+// 主要功能：
+// 1. 按需开启：默认关闭，开启后才记录请求
+// 2. 请求记录：方法、路径、站点、状态码、字节数与耗时，响应体发送完毕或连接断开时记录
+// 3. 实时推送与查询：通过 Tauri Channel 推送，并在有界环形缓冲区中保留最近的记录
+
+use std::{collections::VecDeque, sync::Arc, time::Instant};
+
+use axum::{
+    body::Body,
+    extract::{Request, State as AxumState},
+    middleware::Next,
+    response::Response,
+};
+use futures::StreamExt;
+use serde::Serialize;
+use tauri::{ipc::Channel, State as TauriState};
+use tokio::sync::Mutex;
+
+use super::{lan, now_millis, AppState, ServerState};
+use crate::commands::AppResult;
+
+/// 环形缓冲区容量
+const MAX_ENTRIES: usize = 2000;
+
+/// 访问日志条目
+/// 包含：
+/// - id: 自增序号，用于前端去重和排序
+/// - method: 请求方法
+/// - path: 请求路径（子域名请求为改写后的路径）
+/// - site: 站点ID，非站点请求时为 None
+/// - status: 响应状态码
+/// - bytes: 已发送的响应体字节数，客户端提前断开时为断开前发送的字节数
+/// - latency_ms: 从收到请求到响应体发送完毕（或连接断开）的耗时（毫秒）
+/// - time: 请求时间（Unix 毫秒）
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccessLogEntry {
+    id: u64,
+    method: String,
+    path: String,
+    site: Option<String>,
+    status: u16,
+    bytes: u64,
+    latency_ms: f64,
+    time: u64,
+}
+
+/// 访问日志状态
+/// 包含：
+/// - enabled: 是否记录请求
+/// - next_id: 下一条记录的序号
+/// - entries: 最近的请求记录
+/// - subscribers: 访问日志的订阅通道
+#[derive(Default)]
+pub(super) struct AccessLog {
+    enabled: bool,
+    next_id: u64,
+    entries: VecDeque<AccessLogEntry>,
+    subscribers: Vec<Channel<AccessLogEntry>>,
+}
+
+impl AccessLog {
+    /// 追加记录并推送给订阅者，推送失败的通道视为已关闭并移除
+    fn push(&mut self, mut entry: AccessLogEntry) {
+        entry.id = self.next_id;
+        self.next_id += 1;
+
+        if self.entries.len() >= MAX_ENTRIES {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(entry.clone());
+
+        self.subscribers
+            .retain(|channel| channel.send(entry.clone()).is_ok());
+    }
+}
+
+/// 发送中的请求
+/// 随响应体一起释放，释放时（响应体发送完毕或连接断开）补全字节数与耗时并写入访问日志
+struct PendingEntry {
+    state: Arc<AppState>,
+    entry: AccessLogEntry,
+    started: Instant,
+}
+
+impl PendingEntry {
+    /// 累计发送的字节数
+    fn add_bytes(&mut self, len: usize) {
+        self.entry.bytes += len as u64;
+    }
+}
+
+impl Drop for PendingEntry {
+    fn drop(&mut self) {
+        let mut entry = self.entry.clone();
+        entry.latency_ms = self.started.elapsed().as_secs_f64() * 1000.0;
+
+        // 释放时不能等待锁，交给运行时异步写入；运行时已关闭时丢弃该记录
+        let Ok(handle) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+        let state = self.state.clone();
+        handle.spawn(async move {
+            let mut access_log = state.access_log.lock().await;
+            if access_log.enabled {
+                access_log.push(entry);
+            }
+        });
+    }
+}
+
+/// 访问日志中间件
+/// 未开启时仅检查开关后直接放行；开启时包装响应体，统计发送的字节数，
+/// 并在响应体发送完毕或被丢弃时记录
+pub(super) async fn log_requests(
+    AxumState(state): AxumState<Arc<AppState>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if !state.access_log.lock().await.enabled {
+        return next.run(request).await;
+    }
+
+    let started = Instant::now();
+    let time = now_millis();
+    let method = request.method().to_string();
+    let path = request.uri().path().to_owned();
+
+    let response = next.run(request).await;
+
+    let mut pending = PendingEntry {
+        state,
+        entry: AccessLogEntry {
+            id: 0,
+            method,
+            site: lan::site_hash_from_path(&path).map(str::to_owned),
+            path,
+            status: response.status().as_u16(),
+            bytes: 0,
+            latency_ms: 0.0,
+            time,
+        },
+        started,
+    };
+
+    response.map(|body| {
+        let counted = body.into_data_stream().inspect(move |chunk| {
+            if let Ok(bytes) = chunk {
+                pending.add_bytes(bytes.len());
+            }
+        });
+        Body::from_stream(counted)
+    })
+}
+
+/// 开启或关闭访问日志
+/// 关闭时保留已有记录
+///
+/// 参数：
+/// - state: 服务器状态
+/// - enabled: 是否开启
+///
+/// 返回：
+/// - 成功：空值
+/// - 失败：错误信息
+#[tauri::command]
+pub async fn set_access_log_enabled(
+    state: TauriState<'_, Mutex<ServerState>>,
+    enabled: bool,
+) -> AppResult<()> {
+    let state_guard = state.lock().await;
+    state_guard.app_state.access_log.lock().await.enabled = enabled;
+    Ok(())
+}
+
+/// 订阅访问日志
+/// 开启访问日志后，每个请求完成时都会通过该通道推送，不再需要时调用 unsubscribe_access_log 退订
+///
+/// 参数：
+/// - state: 服务器状态
+/// - on_entry: 访问日志推送通道
+///
+/// 返回：
+/// - 成功：空值
+/// - 失败：错误信息
+#[tauri::command]
+pub async fn subscribe_access_log(
+    state: TauriState<'_, Mutex<ServerState>>,
+    on_entry: Channel<AccessLogEntry>,
+) -> AppResult<()> {
+    let state_guard = state.lock().await;
+    state_guard
+        .app_state
+        .access_log
+        .lock()
+        .await
+        .subscribers
+        .push(on_entry);
+
+    Ok(())
+}
+
+/// 退订访问日志
+///
+/// 参数：
+/// - state: 服务器状态
+/// - channel_id: 订阅时传入的推送通道ID
+///
+/// 返回：
+/// - 成功：空值，通道未订阅时同样成功
+/// - 失败：错误信息
+#[tauri::command]
+pub async fn unsubscribe_access_log(
+    state: TauriState<'_, Mutex<ServerState>>,
+    channel_id: u32,
+) -> AppResult<()> {
+    let state_guard = state.lock().await;
+    state_guard
+        .app_state
+        .access_log
+        .lock()
+        .await
+        .subscribers
+        .retain(|channel| channel.id() != channel_id);
+
+    Ok(())
+}
+
+/// 查询访问日志
+/// 按时间顺序返回环形缓冲区中的记录
+///
+/// 参数：
+/// - state: 服务器状态
+/// - path: 静态站点目录路径，指定时只返回该站点的记录
+/// - limit: 最多返回的条数，指定时返回最近的记录
+///
+/// 返回：
+/// - 成功：访问日志列表
+/// - 失败：错误信息
+#[tauri::command]
+pub async fn get_access_log(
+    state: TauriState<'_, Mutex<ServerState>>,
+    path: Option<String>,
+    limit: Option<usize>,
+) -> AppResult<Vec<AccessLogEntry>> {
+    let state_guard = state.lock().await;
+    let site = match path {
+        Some(path) => Some(state_guard.resolve_site(&path)?.0),
+        None => None,
+    };
+
+    let access_log = state_guard.app_state.access_log.lock().await;
+    let mut entries: Vec<_> = access_log
+        .entries
+        .iter()
+        .rev()
+        .filter(|entry| site.is_none() || entry.site == site)
+        .take(limit.unwrap_or(MAX_ENTRIES))
+        .cloned()
+        .collect();
+    entries.reverse();
+
+    Ok(entries)
+}
+
+/// 清空访问日志
+///
+/// 参数：
+/// - state: 服务器状态
+///
+/// 返回：
+/// - 成功：空值
+/// - 失败：错误信息
+#[tauri::command]
+pub async fn clear_access_log(state: TauriState<'_, Mutex<ServerState>>) -> AppResult<()> {
+    let state_guard = state.lock().await;
+    state_guard
+        .app_state
+        .access_log
+        .lock()
+        .await
+        .entries
+        .clear();
+    Ok(())
+}
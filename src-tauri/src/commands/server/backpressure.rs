@@ -0,0 +1,183 @@
+// 消息背压模块：限制每个客户端的待发送消息，并帮助落后的客户端恢复同步
+// 主要功能：
+// 1. 有界队列：单播消息使用有界队列，队列已满时丢弃新消息
+// 2. 落后统计：按客户端统计因处理过慢而跳过的广播消息数和被丢弃的单播消息数
+// 3. 状态重放：记录编辑器最近发出的场景跳转，向落后的客户端重放以恢复同步
+
+use std::{
+    collections::HashMap,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use axum::extract::ws::Message;
+use serde::Serialize;
+use tauri::State as TauriState;
+use tokio::sync::{mpsc::error::TrySendError, Mutex};
+
+use super::{
+    recorder::{Direction, Target},
+    ServerState, UnicastClient,
+};
+use crate::commands::{AppError, AppResult};
+
+/// 每个客户端单播队列的容量
+pub(super) const UNICAST_CAPACITY: usize = 64;
+
+/// 客户端消息统计
+/// 由发送任务和单播发送方共同更新
+#[derive(Default)]
+pub(super) struct ClientStats {
+    lagged: AtomicU64,
+    dropped: AtomicU64,
+}
+
+impl ClientStats {
+    /// 记录客户端落后而跳过的广播消息
+    pub(super) fn record_lag(&self, skipped: u64) {
+        self.lagged.fetch_add(skipped, Ordering::Relaxed);
+    }
+
+    /// 记录因队列已满而丢弃的单播消息
+    fn record_drop(&self) {
+        self.dropped.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// 客户端消息统计快照
+/// 包含：
+/// - client_id: 客户端ID
+/// - lagged: 因处理过慢而跳过的广播消息数
+/// - dropped: 因队列已满而丢弃的单播消息数
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClientQueueStats {
+    client_id: String,
+    lagged: u64,
+    dropped: u64,
+}
+
+impl UnicastClient {
+    /// 向客户端的单播队列发送消息，队列已满时丢弃并计数
+    pub(super) fn try_send(&self, message: Message) -> AppResult<()> {
+        match self.tx.try_send(message) {
+            Ok(()) => Ok(()),
+            Err(TrySendError::Full(_)) => {
+                self.stats.record_drop();
+                Err(AppError::Server("客户端消息队列已满，消息已丢弃".into()))
+            }
+            Err(TrySendError::Closed(_)) => {
+                Err(AppError::Server("Failed to send unicast message".into()))
+            }
+        }
+    }
+
+    fn queue_stats(&self) -> ClientQueueStats {
+        ClientQueueStats {
+            client_id: self.info.id.clone(),
+            lagged: self.stats.lagged.load(Ordering::Relaxed),
+            dropped: self.stats.dropped.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// 编辑器最近发出的场景状态
+/// 包含：
+/// - next_seq: 下一条记录的序号，用于比较全局与站点记录的先后
+/// - global: 最近一次全局广播的场景状态
+/// - sites: 各站点最近一次站点广播的场景状态，键为站点哈希
+#[derive(Default)]
+pub(super) struct LatestScenes {
+    next_seq: u64,
+    global: Option<(u64, String)>,
+    sites: HashMap<String, (u64, String)>,
+}
+
+impl LatestScenes {
+    /// 记录场景状态
+    ///
+    /// 参数：
+    /// - site: 站点哈希，为 None 时表示全局广播
+    /// - json: 已序列化的调试消息
+    pub(super) fn record(&mut self, site: Option<&str>, json: String) {
+        let entry = (self.next_seq, json);
+        self.next_seq += 1;
+
+        match site {
+            Some(site) => {
+                self.sites.insert(site.to_string(), entry);
+            }
+            None => self.global = Some(entry),
+        }
+    }
+
+    /// 清除站点的场景状态
+    pub(super) fn clear_site(&mut self, hash: &str) {
+        self.sites.remove(hash);
+    }
+
+    /// 获取客户端应处于的最新场景状态，取全局与所属站点记录中较新的一条
+    fn latest_for(&self, site: Option<&str>) -> Option<&str> {
+        let site_entry = site.and_then(|site| self.sites.get(site));
+        [self.global.as_ref(), site_entry]
+            .into_iter()
+            .flatten()
+            .max_by_key(|(seq, _)| *seq)
+            .map(|(_, json)| json.as_str())
+    }
+}
+
+/// 获取各客户端的消息统计
+///
+/// 参数：
+/// - state: 服务器状态
+///
+/// 返回：
+/// - 成功：客户端消息统计列表
+/// - 失败：错误信息
+#[tauri::command]
+pub async fn get_client_stats(
+    state: TauriState<'_, Mutex<ServerState>>,
+) -> AppResult<Vec<ClientQueueStats>> {
+    let state_guard = state.lock().await;
+    let clients = state_guard.app_state.unicast_clients.lock().await;
+
+    let mut stats: Vec<_> = clients.values().map(UnicastClient::queue_stats).collect();
+    stats.sort_by(|a, b| a.client_id.cmp(&b.client_id));
+
+    Ok(stats)
+}
+
+/// 重新同步客户端
+/// 向客户端重放编辑器最近发出的场景跳转，使落后的客户端回到当前位置
+///
+/// 参数：
+/// - state: 服务器状态
+/// - client_id: 目标客户端ID
+///
+/// 返回：
+/// - 成功：空值
+/// - 失败：错误信息（客户端不存在、尚无可重放的场景状态或队列已满）
+#[tauri::command]
+pub async fn resync_client(
+    state: TauriState<'_, Mutex<ServerState>>,
+    client_id: String,
+) -> AppResult<()> {
+    let state_guard = state.lock().await;
+    let clients = state_guard.app_state.unicast_clients.lock().await;
+    let client = clients
+        .get(&client_id)
+        .ok_or_else(|| AppError::Server("Client not found".into()))?;
+
+    let latest_scenes = state_guard.app_state.latest_scenes.lock().await;
+    let json = latest_scenes
+        .latest_for(client.info.site.as_deref())
+        .ok_or_else(|| AppError::Server("尚无可重放的场景状态".into()))?;
+
+    let message = Message::Text(json.into());
+    state_guard.app_state.sessions.lock().await.record(
+        Direction::FromServer,
+        Target::Client(client_id),
+        &message,
+    );
+    client.try_send(message)
+}
@@ -0,0 +1,310 @@
+// WebGAL Craft 编辑器桥接脚本
+// 由预览服务器注入到站点首页，向编辑器上报当前场景、回想记录、运行时错误、控制台输出和资源加载耗时。
+// 优先复用游戏自身连接到 /api/webgalsync 的 WebSocket，游戏未连接时自行建立连接。
+// 游戏的同步连接在刷新或重连后沿用上次的客户端ID（保存在 sessionStorage 中）。
+(function () {
+  'use strict'
+
+  if (window.__WEBGAL_CRAFT_BRIDGE__) {
+    return
+  }
+  window.__WEBGAL_CRAFT_BRIDGE__ = true
+
+  var EVENT = 'webgalCraftBridge'
+  var SYNC_PATH = '/api/webgalsync'
+  var SYNC_FROM_CLIENT = 1
+  var MAX_PENDING = 200
+  var MAX_TEXT_LENGTH = 2000
+  var FALLBACK_DELAY = 3000
+  var RESOURCE_BATCH_DELAY = 500
+  var CLIENT_ID_KEY = 'webgal-craft-client-id'
+  var BACKLOG_DELAY = 300
+  var MAX_BACKLOG = 100
+
+  var NativeWebSocket = window.WebSocket
+  var nativeSend = NativeWebSocket.prototype.send
+  var socket = null
+  var pending = []
+  var lastScene = null
+  var lastBacklog = null
+  var backlogTimer = null
+
+  function isSyncUrl(url) {
+    try {
+      return new URL(url, location.href).pathname === SYNC_PATH
+    } catch (e) {
+      return false
+    }
+  }
+
+  function flush() {
+    if (!socket || socket.readyState !== NativeWebSocket.OPEN) {
+      return
+    }
+    while (pending.length > 0) {
+      nativeSend.call(socket, pending.shift())
+    }
+  }
+
+  function report(type, data) {
+    var message
+    try {
+      message = JSON.stringify({ event: EVENT, data: { type: type, data: data } })
+    } catch (e) {
+      return
+    }
+    if (pending.length >= MAX_PENDING) {
+      pending.shift()
+    }
+    pending.push(message)
+    flush()
+  }
+
+  function adopt(ws) {
+    if (socket && socket.readyState <= NativeWebSocket.OPEN) {
+      return
+    }
+    socket = ws
+    ws.addEventListener('open', flush)
+    flush()
+  }
+
+  // 在同步连接地址上附加所属站点，使服务器在 Cookie 不可用时（如跨站 iframe）也能识别站点
+  function withSite(url) {
+    var site = window.__WEBGAL_CRAFT_SITE__
+    try {
+      var parsed = new URL(url, location.href)
+      if (site && !parsed.searchParams.has('site')) {
+        parsed.searchParams.set('site', site)
+      }
+      return parsed.href
+    } catch (e) {
+      return url
+    }
+  }
+
+  // 在同步连接地址上附加上次的客户端ID，服务器在该ID未被占用时沿用
+  function withClientId(url) {
+    var id = null
+    try {
+      id = sessionStorage.getItem(CLIENT_ID_KEY)
+    } catch (e) {
+      return url
+    }
+    try {
+      var parsed = new URL(url, location.href)
+      if (id && !parsed.searchParams.has('client_id')) {
+        parsed.searchParams.set('client_id', id)
+      }
+      return parsed.href
+    } catch (e) {
+      return url
+    }
+  }
+
+  // 从服务器的握手消息中记录本次分配的客户端ID
+  function rememberClientId(event) {
+    if (typeof event.data !== 'string' || event.data.indexOf('"connected"') === -1) {
+      return
+    }
+    var message
+    try {
+      message = JSON.parse(event.data)
+    } catch (e) {
+      return
+    }
+    if (message && message.event === 'connected' && message.data && message.data.clientId) {
+      try {
+        sessionStorage.setItem(CLIENT_ID_KEY, message.data.clientId)
+      } catch (e) {
+        // 存储不可用（如禁用了 Cookie）时，下次连接由服务器分配新的ID
+      }
+    }
+  }
+
+  // 捕获游戏创建的同步连接，并从游戏发出的 SyncFromClient 消息中读取当前场景
+  function BridgedWebSocket(url, protocols) {
+    var isSync = isSyncUrl(url)
+    if (isSync) {
+      url = withClientId(withSite(url))
+    }
+    var ws = protocols === undefined ? new NativeWebSocket(url) : new NativeWebSocket(url, protocols)
+    if (isSync) {
+      ws.addEventListener('message', rememberClientId)
+      adopt(ws)
+    }
+    return ws
+  }
+  BridgedWebSocket.prototype = NativeWebSocket.prototype
+  ;['CONNECTING', 'OPEN', 'CLOSING', 'CLOSED'].forEach(function (key) {
+    BridgedWebSocket[key] = NativeWebSocket[key]
+  })
+  window.WebSocket = BridgedWebSocket
+
+  NativeWebSocket.prototype.send = function (data) {
+    if (this === socket && typeof data === 'string') {
+      observeSync(data)
+    }
+    return nativeSend.apply(this, arguments)
+  }
+
+  function observeSync(text) {
+    var message
+    try {
+      message = JSON.parse(text)
+    } catch (e) {
+      return
+    }
+    var data = message && message.data
+    if (!data || data.command !== SYNC_FROM_CLIENT || !data.sceneMsg) {
+      return
+    }
+    var scene = data.sceneMsg.scene
+    var sentence = data.sceneMsg.sentence
+    var key = scene + ':' + sentence
+    if (key !== lastScene) {
+      lastScene = key
+      report('scene', { scene: scene, sentence: sentence })
+      scheduleBacklog()
+    }
+  }
+
+  // 读取 WebGAL 运行时的回想记录，引擎未暴露运行时对象（window.WebGAL）时返回 null
+  function readBacklog() {
+    var core = window.WebGAL
+    var manager = core && core.backlogManager
+    if (!manager) {
+      return null
+    }
+    var items = typeof manager.getBacklog === 'function' ? manager.getBacklog() : manager.backlog
+    return Array.isArray(items) ? items : null
+  }
+
+  // 只保留回想记录中编辑器需要的字段，舞台状态体积较大，不随回想记录上报
+  function backlogEntry(item) {
+    var stage = (item && item.currentStageState) || {}
+    var entry = (item && item.saveScene) || {}
+    return {
+      scene: typeof entry.sceneName === 'string' ? entry.sceneName : null,
+      sentence: typeof item.currentStatementId === 'number' ? item.currentStatementId : null,
+      speaker: typeof stage.showName === 'string' && stage.showName ? stringify(stage.showName) : null,
+      text: typeof stage.showText === 'string' ? stringify(stage.showText) : '',
+      vocal: typeof stage.vocal === 'string' && stage.vocal ? stage.vocal : null,
+    }
+  }
+
+  // 语句变化后稍等引擎写入回想记录，再上报最近的记录，内容未变化时不重复上报
+  function scheduleBacklog() {
+    if (backlogTimer) {
+      return
+    }
+    backlogTimer = setTimeout(function () {
+      backlogTimer = null
+      var items
+      try {
+        items = readBacklog()
+      } catch (e) {
+        return
+      }
+      if (!items) {
+        return
+      }
+      var entries = items.slice(-MAX_BACKLOG).map(backlogEntry)
+      var key = items.length + ':' + JSON.stringify(entries[entries.length - 1] || null)
+      if (key !== lastBacklog) {
+        lastBacklog = key
+        report('backlog', { entries: entries })
+      }
+    }, BACKLOG_DELAY)
+  }
+
+  // 游戏未建立同步连接时自行连接，并标记为桥接连接，使服务器不将其视为预览客户端
+  setTimeout(function () {
+    if (!socket) {
+      var protocol = location.protocol === 'https:' ? 'wss:' : 'ws:'
+      adopt(new NativeWebSocket(withSite(protocol + '//' + location.host + SYNC_PATH + '?bridge=1')))
+    }
+  }, FALLBACK_DELAY)
+
+  function stringify(value) {
+    var text
+    if (value instanceof Error) {
+      text = value.stack || String(value)
+    } else if (typeof value === 'string') {
+      text = value
+    } else {
+      try {
+        text = JSON.stringify(value)
+      } catch (e) {
+        text = String(value)
+      }
+    }
+    if (text === undefined) {
+      text = String(value)
+    }
+    return text.length > MAX_TEXT_LENGTH ? text.slice(0, MAX_TEXT_LENGTH) + '…' : text
+  }
+
+  // 运行时错误
+  window.addEventListener('error', function (event) {
+    if (!event.message) {
+      return
+    }
+    report('error', {
+      message: stringify(event.message),
+      source: event.filename || null,
+      line: event.lineno || null,
+      column: event.colno || null,
+      stack: event.error && event.error.stack ? stringify(event.error.stack) : null,
+    })
+  })
+  window.addEventListener('unhandledrejection', function (event) {
+    var reason = event.reason
+    report('error', {
+      message: stringify(reason && reason.message ? reason.message : reason),
+      source: null,
+      line: null,
+      column: null,
+      stack: reason && reason.stack ? stringify(reason.stack) : null,
+    })
+  })
+
+  // 控制台输出
+  ;['log', 'info', 'warn', 'error', 'debug'].forEach(function (level) {
+    var original = console[level]
+    if (typeof original !== 'function') {
+      return
+    }
+    console[level] = function () {
+      var args = Array.prototype.slice.call(arguments)
+      report('console', { level: level, args: args.map(stringify) })
+      return original.apply(this, arguments)
+    }
+  })
+
+  // 资源加载耗时，短时间内的记录合并上报
+  if (typeof PerformanceObserver === 'function') {
+    var resources = []
+    var timer = null
+    try {
+      new PerformanceObserver(function (list) {
+        list.getEntries().forEach(function (entry) {
+          resources.push({
+            name: entry.name,
+            initiatorType: entry.initiatorType,
+            duration: entry.duration,
+            transferSize: typeof entry.transferSize === 'number' ? entry.transferSize : null,
+          })
+        })
+        if (!timer) {
+          timer = setTimeout(function () {
+            timer = null
+            report('resources', { entries: resources.splice(0) })
+          }, RESOURCE_BATCH_DELAY)
+        }
+      }).observe({ type: 'resource', buffered: true })
+    } catch (e) {
+      // 不支持 resource 类型时不上报资源耗时
+    }
+  }
+})()
@@ -0,0 +1,293 @@
+// 编辑器桥接模块：向站点首页注入桥接脚本，收集游戏的运行时信息
+// 主要功能：
+// 1. 脚本注入：提供站点首页时即时改写 HTML，不修改磁盘上的引擎文件
+// 2. 运行时上报：桥接脚本通过 /api/webgalsync 上报当前场景、回想记录、运行时错误、控制台输出和资源加载耗时
+// 3. 上报校验：定义上报消息的类型，并随客户端消息一起转发给编辑器
+// 4. 存档同步：按站点选项一并注入存档同步脚本（见 saves 子模块）
+
+use axum::{
+    body::{Body, Bytes},
+    http::{
+        header::{
+            ACCEPT_RANGES, CONTENT_LENGTH, CONTENT_TYPE, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH,
+            IF_RANGE, LAST_MODIFIED, RANGE,
+        },
+        HeaderMap, StatusCode,
+    },
+    response::Response,
+};
+use futures::{stream, StreamExt};
+use serde::{Deserialize, Serialize};
+
+use super::{saves, SiteOptions};
+
+/// 桥接脚本上报消息的事件名，用于与 WebGAL 调试消息区分
+pub(super) const BRIDGE_EVENT: &str = "webgalCraftBridge";
+
+/// 桥接脚本
+const BRIDGE_SCRIPT: &str = include_str!("bridge.js");
+
+/// 允许改写的首页最大字节数
+const MAX_DOCUMENT_SIZE: usize = 8 * 1024 * 1024;
+
+/// 运行时错误
+/// - message: 错误信息
+/// - source: 出错的脚本地址
+/// - line: 行号
+/// - column: 列号
+/// - stack: 调用栈
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RuntimeError {
+    message: String,
+    source: Option<String>,
+    line: Option<u32>,
+    column: Option<u32>,
+    stack: Option<String>,
+}
+
+/// 控制台输出级别
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ConsoleLevel {
+    Log,
+    Info,
+    Warn,
+    Error,
+    Debug,
+}
+
+/// 资源加载耗时
+/// - name: 资源地址
+/// - initiator_type: 发起加载的类型，如 img、fetch、xmlhttprequest
+/// - duration: 加载耗时（毫秒）
+/// - transfer_size: 传输字节数，浏览器无法提供时为 None
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceTiming {
+    name: String,
+    initiator_type: String,
+    duration: f64,
+    transfer_size: Option<u64>,
+}
+
+/// 回想记录条目
+/// - scene: 所在场景
+/// - sentence: 语句序号
+/// - speaker: 说话人
+/// - text: 对话文本
+/// - vocal: 语音文件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BacklogEntry {
+    scene: Option<String>,
+    sentence: Option<u32>,
+    speaker: Option<String>,
+    text: String,
+    vocal: Option<String>,
+}
+
+/// 桥接脚本的上报消息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", tag = "type", content = "data")]
+pub enum BridgeReport {
+    /// 当前场景与语句
+    Scene { scene: String, sentence: u32 },
+    /// 运行时错误与未处理的 Promise 拒绝
+    Error(RuntimeError),
+    /// 控制台输出，参数已转换为文本
+    Console {
+        level: ConsoleLevel,
+        args: Vec<String>,
+    },
+    /// 一批资源加载耗时
+    Resources { entries: Vec<ResourceTiming> },
+    /// 最近的回想记录，按时间顺序排列
+    Backlog { entries: Vec<BacklogEntry> },
+}
+
+/// 移除首页请求的条件请求与范围请求头
+/// 注入后的内容与磁盘文件不同，不能沿用基于磁盘文件的缓存协商和分段读取
+pub(super) fn strip_conditional_headers(headers: &mut HeaderMap) {
+    for name in [IF_NONE_MATCH, IF_MODIFIED_SINCE, IF_RANGE, RANGE] {
+        headers.remove(name);
+    }
+}
+
+/// 按站点选项生成需要注入首页的脚本
+/// 脚本之前先写入站点ID，供脚本访问站点相关的接口
+///
+/// 参数：
+/// - hash: 站点ID
+/// - options: 站点选项
+///
+/// 返回：需要注入的 HTML 片段，没有开启任何注入时为 None
+pub(super) fn document_scripts(hash: &str, options: &SiteOptions) -> Option<String> {
+    let scripts: Vec<_> = [
+        (options.bridge, BRIDGE_SCRIPT),
+        (options.persist_saves, saves::SAVES_SCRIPT),
+    ]
+    .into_iter()
+    .filter(|(enabled, _)| *enabled)
+    .map(|(_, script)| format!("<script>{script}</script>"))
+    .collect();
+
+    if scripts.is_empty() {
+        return None;
+    }
+
+    let site = serde_json::to_string(hash).ok()?;
+    Some(format!(
+        "<script>window.__WEBGAL_CRAFT_SITE__ = {site}</script>{}",
+        scripts.concat()
+    ))
+}
+
+/// 向首页响应注入脚本
+/// 脚本插入在 `<head>` 开头，早于游戏脚本执行；没有 `<head>` 时插入在文档开头。
+/// 非 HTML 或非 200 的响应原样返回；首页超过大小上限或读取失败时，返回原始内容而不注入
+pub(super) async fn inject_scripts(response: Response, scripts: &str) -> Response {
+    let is_html = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("text/html"));
+    if response.status() != StatusCode::OK || !is_html {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let too_large = parts
+        .headers
+        .get(CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok()?.parse::<usize>().ok())
+        .is_some_and(|len| len > MAX_DOCUMENT_SIZE);
+    if too_large {
+        return Response::from_parts(parts, body);
+    }
+
+    // 逐块读取首页，超过大小上限或读取出错时，将已读取的内容与剩余部分原样拼接返回
+    let mut chunks = body.into_data_stream();
+    let mut html = Vec::new();
+    while let Some(chunk) = chunks.next().await {
+        let rest = match chunk {
+            Ok(chunk) => {
+                html.extend_from_slice(&chunk);
+                if html.len() <= MAX_DOCUMENT_SIZE {
+                    continue;
+                }
+                chunks.boxed()
+            }
+            Err(e) => stream::once(async { Err(e) }).chain(chunks).boxed(),
+        };
+        let read = stream::once(async { Ok(Bytes::from(html)) });
+        return Response::from_parts(parts, Body::from_stream(read.chain(rest)));
+    }
+
+    let insert_at = head_content_start(&html).unwrap_or(0);
+    let injected = [&html[..insert_at], scripts.as_bytes(), &html[insert_at..]].concat();
+
+    for name in [CONTENT_LENGTH, ETAG, LAST_MODIFIED, ACCEPT_RANGES] {
+        parts.headers.remove(name);
+    }
+
+    Response::from_parts(parts, Body::from(injected))
+}
+
+/// 查找 `<head>` 开始标签之后的字节位置
+/// 按字节查找，不要求首页是合法的 UTF-8
+fn head_content_start(html: &[u8]) -> Option<usize> {
+    let lower = html.to_ascii_lowercase();
+    let mut offset = 0;
+
+    while let Some(found) = find_bytes(&lower[offset..], b"<head") {
+        let after_name = offset + found + b"<head".len();
+        // 排除 <header> 等同前缀的标签
+        if matches!(
+            lower.get(after_name),
+            Some(b'>' | b' ' | b'\t' | b'\n' | b'\r' | b'/')
+        ) {
+            return lower[after_name..]
+                .iter()
+                .position(|&byte| byte == b'>')
+                .map(|end| after_name + end + 1);
+        }
+        offset = after_name;
+    }
+
+    None
+}
+
+/// 查找字节串首次出现的位置
+fn find_bytes(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{
+        body::{to_bytes, Body},
+        http::{header::CONTENT_TYPE, StatusCode},
+        response::Response,
+    };
+    use futures::executor::block_on;
+
+    use super::{head_content_start, inject_scripts, MAX_DOCUMENT_SIZE};
+
+    const SCRIPTS: &str = "<script>bridge()</script>";
+
+    fn html_response(body: impl Into<Body>) -> Response {
+        Response::builder()
+            .status(StatusCode::OK)
+            .header(CONTENT_TYPE, "text/html; charset=utf-8")
+            .body(body.into())
+            .unwrap()
+    }
+
+    fn inject(body: Vec<u8>) -> Vec<u8> {
+        block_on(async {
+            let response = inject_scripts(html_response(body), SCRIPTS).await;
+            to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap()
+                .to_vec()
+        })
+    }
+
+    #[test]
+    fn finds_head_start() {
+        assert_eq!(head_content_start(b"<html><head><title>"), Some(12));
+        assert_eq!(head_content_start(b"<HEAD lang=\"zh\">x"), Some(16));
+        assert_eq!(head_content_start(b"<header></header><head>"), Some(23));
+        assert_eq!(head_content_start(b"<body></body>"), None);
+    }
+
+    #[test]
+    fn injects_into_head_preserving_bytes() {
+        let mut page = b"<html><head><title>".to_vec();
+        page.extend_from_slice(&[0xff, 0xfe]);
+        page.extend_from_slice("标题</title></head></html>".as_bytes());
+
+        let mut expected = b"<html><head>".to_vec();
+        expected.extend_from_slice(SCRIPTS.as_bytes());
+        expected.extend_from_slice(&page[b"<html><head>".len()..]);
+
+        assert_eq!(inject(page), expected);
+    }
+
+    #[test]
+    fn injects_at_start_without_head() {
+        assert_eq!(
+            inject(b"<body>hi</body>".to_vec()),
+            format!("{SCRIPTS}<body>hi</body>").into_bytes()
+        );
+    }
+
+    #[test]
+    fn serves_oversized_page_unchanged() {
+        let page = vec![b'a'; MAX_DOCUMENT_SIZE + 1];
+        assert_eq!(inject(page.clone()), page);
+    }
+}
@@ -0,0 +1,333 @@
+// 请求诊断模块：记录预览站点的失败请求，帮助定位缺失的资源
+// 主要功能：
+// 1. 错误记录：按站点记录状态码为 4xx/5xx 的响应（路径、状态码、来源页面、时间），只记录已注册的站点
+// 2. 实时推送：通过 Tauri Channel 将失败请求推送给编辑器，编辑器可随时退订
+// 3. 缺失文件汇总：按路径聚合 404 响应，文件补齐后自动移出列表
+
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    sync::Arc,
+};
+
+use axum::{
+    extract::{Request, State as AxumState},
+    http::header::REFERER,
+    middleware::Next,
+    response::Response,
+};
+use serde::Serialize;
+use tauri::{ipc::Channel, State as TauriState};
+use tokio::sync::Mutex;
+
+use super::{lan, now_millis, AppState, ServerState};
+use crate::commands::AppResult;
+
+/// 每个站点保留的失败请求记录数量
+const MAX_ERRORS_PER_SITE: usize = 500;
+
+/// 每个站点保留的缺失文件数量，达到上限后不再加入新的路径
+const MAX_MISSING_PER_SITE: usize = 1000;
+
+/// 每个缺失文件保留的来源页面数量
+const MAX_REFERERS_PER_FILE: usize = 10;
+
+/// 失败请求
+/// 包含：
+/// - site: 站点ID
+/// - path: 相对于站点根目录的请求路径
+/// - status: 响应状态码
+/// - referer: 发起请求的页面
+/// - time: 请求时间（Unix 毫秒）
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RequestError {
+    site: String,
+    path: String,
+    status: u16,
+    referer: Option<String>,
+    time: u64,
+}
+
+/// 缺失文件
+/// 包含：
+/// - path: 相对于站点根目录的文件路径
+/// - count: 请求次数
+/// - referers: 请求过该文件的页面
+/// - first_seen: 首次请求时间（Unix 毫秒）
+/// - last_seen: 最近请求时间（Unix 毫秒）
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MissingFile {
+    path: String,
+    count: u32,
+    referers: Vec<String>,
+    first_seen: u64,
+    last_seen: u64,
+}
+
+/// 单个站点的诊断记录
+#[derive(Default)]
+struct SiteDiagnostics {
+    errors: VecDeque<RequestError>,
+    missing: BTreeMap<String, MissingFile>,
+}
+
+impl SiteDiagnostics {
+    /// 记录失败请求，404 同时计入缺失文件
+    fn record(&mut self, error: &RequestError) {
+        if self.errors.len() >= MAX_ERRORS_PER_SITE {
+            self.errors.pop_front();
+        }
+        self.errors.push_back(error.clone());
+
+        if error.status != 404 {
+            return;
+        }
+        if self.missing.len() >= MAX_MISSING_PER_SITE && !self.missing.contains_key(&error.path) {
+            return;
+        }
+
+        let missing = self
+            .missing
+            .entry(error.path.clone())
+            .or_insert_with(|| MissingFile {
+                path: error.path.clone(),
+                count: 0,
+                referers: Vec::new(),
+                first_seen: error.time,
+                last_seen: error.time,
+            });
+        missing.count += 1;
+        missing.last_seen = error.time;
+
+        if let Some(referer) = &error.referer {
+            if !missing.referers.contains(referer) && missing.referers.len() < MAX_REFERERS_PER_FILE
+            {
+                missing.referers.push(referer.clone());
+            }
+        }
+    }
+}
+
+/// 请求诊断状态
+/// 包含：
+/// - sites: 各站点的诊断记录，键为站点ID
+/// - subscribers: 失败请求的订阅通道
+#[derive(Default)]
+pub(super) struct Diagnostics {
+    sites: HashMap<String, SiteDiagnostics>,
+    subscribers: Vec<Channel<RequestError>>,
+}
+
+impl Diagnostics {
+    /// 清除指定站点的诊断记录
+    pub(super) fn clear_site(&mut self, hash: &str) {
+        self.sites.remove(hash);
+    }
+
+    /// 记录失败请求并推送给订阅者，推送失败的通道视为已关闭并移除
+    fn record(&mut self, error: RequestError) {
+        self.sites
+            .entry(error.site.clone())
+            .or_default()
+            .record(&error);
+
+        self.subscribers
+            .retain(|channel| channel.send(error.clone()).is_ok());
+    }
+
+    /// 请求成功时将该路径移出缺失文件列表
+    fn resolve(&mut self, hash: &str, path: &str) {
+        if let Some(site) = self.sites.get_mut(hash) {
+            site.missing.remove(path);
+        }
+    }
+}
+
+/// 请求诊断中间件
+/// 记录已注册站点下状态码为 4xx/5xx 的响应，重定向与缓存协商（3xx）视为成功；未注册的站点ID直接放行
+pub(super) async fn record_errors(
+    AxumState(state): AxumState<Arc<AppState>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let uri_path = request.uri().path().to_owned();
+    let referer = request
+        .headers()
+        .get(REFERER)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_owned);
+
+    let response = next.run(request).await;
+
+    let Some(hash) = lan::site_hash_from_path(&uri_path) else {
+        return response;
+    };
+    if !state.sites.read().await.contains_key(hash) {
+        return response;
+    }
+    let path = site_relative_path(&uri_path, hash);
+    let status = response.status();
+
+    let mut diagnostics = state.diagnostics.lock().await;
+    if status.is_client_error() || status.is_server_error() {
+        diagnostics.record(RequestError {
+            site: hash.to_string(),
+            path,
+            status: status.as_u16(),
+            referer,
+            time: now_millis(),
+        });
+    } else {
+        diagnostics.resolve(hash, &path);
+    }
+    drop(diagnostics);
+
+    response
+}
+
+/// 获取请求相对于站点根目录的解码路径
+/// 例如：/game/abc/game/figure/%E7%AB%8B%E7%BB%98.png -> game/figure/立绘.png
+fn site_relative_path(uri_path: &str, hash: &str) -> String {
+    let relative = uri_path
+        .strip_prefix("/game/")
+        .and_then(|rest| rest.strip_prefix(hash))
+        .unwrap_or_default()
+        .trim_start_matches('/');
+
+    urlencoding::decode(relative)
+        .map(|decoded| decoded.into_owned())
+        .unwrap_or_else(|_| relative.to_string())
+}
+
+/// 订阅失败请求
+/// 之后所有站点的失败请求都会通过该通道实时推送，不再需要时调用 unsubscribe_request_errors 退订
+///
+/// 参数：
+/// - state: 服务器状态
+/// - on_error: 失败请求推送通道
+///
+/// 返回：
+/// - 成功：空值
+/// - 失败：错误信息
+#[tauri::command]
+pub async fn subscribe_request_errors(
+    state: TauriState<'_, Mutex<ServerState>>,
+    on_error: Channel<RequestError>,
+) -> AppResult<()> {
+    let state_guard = state.lock().await;
+    state_guard
+        .app_state
+        .diagnostics
+        .lock()
+        .await
+        .subscribers
+        .push(on_error);
+
+    Ok(())
+}
+
+/// 退订失败请求
+///
+/// 参数：
+/// - state: 服务器状态
+/// - channel_id: 订阅时传入的推送通道ID
+///
+/// 返回：
+/// - 成功：空值，通道未订阅时同样成功
+/// - 失败：错误信息
+#[tauri::command]
+pub async fn unsubscribe_request_errors(
+    state: TauriState<'_, Mutex<ServerState>>,
+    channel_id: u32,
+) -> AppResult<()> {
+    let state_guard = state.lock().await;
+    state_guard
+        .app_state
+        .diagnostics
+        .lock()
+        .await
+        .subscribers
+        .retain(|channel| channel.id() != channel_id);
+
+    Ok(())
+}
+
+/// 获取站点的失败请求记录
+/// 按时间顺序返回最近的失败请求
+///
+/// 参数：
+/// - state: 服务器状态
+/// - path: 静态站点目录路径
+///
+/// 返回：
+/// - 成功：失败请求列表
+/// - 失败：错误信息
+#[tauri::command]
+pub async fn get_request_errors(
+    state: TauriState<'_, Mutex<ServerState>>,
+    path: String,
+) -> AppResult<Vec<RequestError>> {
+    let state_guard = state.lock().await;
+    let (hash, _) = state_guard.resolve_site(&path)?;
+
+    let diagnostics = state_guard.app_state.diagnostics.lock().await;
+    Ok(diagnostics
+        .sites
+        .get(&hash)
+        .map(|site| site.errors.iter().cloned().collect())
+        .unwrap_or_default())
+}
+
+/// 获取站点的缺失文件列表
+/// 按路径汇总返回 404 的请求，之后请求成功的文件不会出现在列表中
+///
+/// 参数：
+/// - state: 服务器状态
+/// - path: 静态站点目录路径
+///
+/// 返回：
+/// - 成功：缺失文件列表，按路径排序
+/// - 失败：错误信息
+#[tauri::command]
+pub async fn get_missing_files(
+    state: TauriState<'_, Mutex<ServerState>>,
+    path: String,
+) -> AppResult<Vec<MissingFile>> {
+    let state_guard = state.lock().await;
+    let (hash, _) = state_guard.resolve_site(&path)?;
+
+    let diagnostics = state_guard.app_state.diagnostics.lock().await;
+    Ok(diagnostics
+        .sites
+        .get(&hash)
+        .map(|site| site.missing.values().cloned().collect())
+        .unwrap_or_default())
+}
+
+/// 清除站点的失败请求记录和缺失文件列表
+///
+/// 参数：
+/// - state: 服务器状态
+/// - path: 静态站点目录路径
+///
+/// 返回：
+/// - 成功：空值
+/// - 失败：错误信息
+#[tauri::command]
+pub async fn clear_request_errors(
+    state: TauriState<'_, Mutex<ServerState>>,
+    path: String,
+) -> AppResult<()> {
+    let state_guard = state.lock().await;
+    let (hash, _) = state_guard.resolve_site(&path)?;
+
+    state_guard
+        .app_state
+        .diagnostics
+        .lock()
+        .await
+        .clear_site(&hash);
+
+    Ok(())
+}
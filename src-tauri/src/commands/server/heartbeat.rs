@@ -0,0 +1,95 @@
+// 心跳检测模块：检测并清理失去响应的 WebSocket 客户端
+// 主要功能：
+// 1. 心跳配置：可配置的 Ping 发送间隔与超时时间
+// 2. 活跃记录：记录客户端最近一次发来任意消息（含 Pong）的时间
+// 3. 断开原因：区分客户端主动关闭、心跳超时和连接异常
+
+use std::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::now_millis;
+use crate::commands::{AppError, AppResult};
+
+/// 心跳配置
+/// 包含：
+/// - interval: Ping 发送间隔（秒）
+/// - timeout: 超过该时长（秒）未收到客户端任何消息时断开连接
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct HeartbeatOptions {
+    interval: u64,
+    timeout: u64,
+}
+
+impl Default for HeartbeatOptions {
+    fn default() -> Self {
+        Self {
+            interval: 15,
+            timeout: 45,
+        }
+    }
+}
+
+impl HeartbeatOptions {
+    /// 校验配置取值
+    /// 超时时间不能短于发送间隔，否则客户端来不及回复就会被断开
+    pub(super) fn validate(&self) -> AppResult<()> {
+        if self.interval == 0 {
+            return Err(AppError::Server("心跳间隔必须大于 0".into()));
+        }
+        if self.timeout < self.interval {
+            return Err(AppError::Server("心跳超时时间不能短于心跳间隔".into()));
+        }
+        Ok(())
+    }
+
+    pub(super) fn interval(&self) -> Duration {
+        Duration::from_secs(self.interval)
+    }
+
+    /// 超时时间（毫秒），超时时间过大时取 u64::MAX，即永不超时
+    fn timeout_millis(&self) -> u64 {
+        self.timeout.saturating_mul(1000)
+    }
+}
+
+/// 客户端活跃状态
+/// 由接收任务更新，发送任务在每次发送 Ping 前检查
+pub(super) struct Liveness {
+    last_seen: AtomicU64,
+}
+
+impl Liveness {
+    pub(super) fn new() -> Self {
+        Self {
+            last_seen: AtomicU64::new(now_millis()),
+        }
+    }
+
+    /// 记录收到客户端消息
+    pub(super) fn touch(&self) {
+        self.last_seen.store(now_millis(), Ordering::Relaxed);
+    }
+
+    /// 客户端是否已超过超时时间未响应
+    pub(super) fn is_expired(&self, options: &HeartbeatOptions) -> bool {
+        now_millis().saturating_sub(self.last_seen.load(Ordering::Relaxed))
+            > options.timeout_millis()
+    }
+}
+
+/// WebSocket 客户端断开原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DisconnectReason {
+    /// 客户端主动关闭连接
+    Closed,
+    /// 超时未响应心跳，已被服务器断开
+    Timeout,
+    /// 连接读写出错
+    Error,
+}
@@ -0,0 +1,237 @@
+// 运行时状态模块：收集预览发回的运行时状态，供编辑器查看和修改变量
+// 主要功能：
+// 1. 状态快照：从游戏的同步消息（SyncFromClient）中提取当前场景、舞台状态及其中的变量，
+//    并合并桥接脚本上报的回想记录
+// 2. 实时推送：按客户端保留最新快照，并通过 Tauri Channel 推送给编辑器，编辑器可随时退订
+// 3. 变量修改：通过执行指令（setVar）修改预览中的变量
+
+use std::collections::HashMap;
+
+use serde::Serialize;
+use serde_json::{Map, Value};
+use tauri::{ipc::Channel, State as TauriState};
+use tokio::sync::Mutex;
+
+use super::{
+    bridge::{BacklogEntry, BridgeReport},
+    now_millis,
+    protocol::{DebugMessage, SceneMsg},
+    ClientPayload, ServerState,
+};
+use crate::commands::{AppError, AppResult};
+
+/// 舞台状态中保存游戏变量的字段名
+const STAGE_VARIABLES_KEY: &str = "GameVar";
+
+/// 运行时状态快照
+/// 包含：
+/// - client_id: 客户端ID
+/// - scene: 当前场景与语句
+/// - variables: 游戏变量，取自舞台状态的 GameVar 字段
+/// - backlog: 回想记录，由桥接脚本上报
+/// - stage: 舞台状态
+/// - updated_at: 最近更新时间（Unix 毫秒）
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StateSnapshot {
+    client_id: String,
+    scene: Option<SceneMsg>,
+    variables: Map<String, Value>,
+    backlog: Vec<BacklogEntry>,
+    stage: Option<Value>,
+    updated_at: u64,
+}
+
+impl StateSnapshot {
+    fn new(client_id: &str) -> Self {
+        Self {
+            client_id: client_id.to_string(),
+            scene: None,
+            variables: Map::new(),
+            backlog: Vec::new(),
+            stage: None,
+            updated_at: now_millis(),
+        }
+    }
+
+    /// 合并同步消息中的状态
+    /// 同步消息不含舞台状态时保留上一次的舞台状态与变量
+    fn merge(&mut self, scene: &SceneMsg, stage: Option<&Value>) {
+        self.scene = Some(scene.clone());
+        if let Some(stage) = stage {
+            if let Some(variables) = stage.get(STAGE_VARIABLES_KEY).and_then(Value::as_object) {
+                self.variables = variables.clone();
+            }
+            self.stage = Some(stage.clone());
+        }
+        self.updated_at = now_millis();
+    }
+}
+
+/// 运行时状态
+/// 包含：
+/// - snapshots: 各客户端的最新快照，键为客户端ID
+/// - subscribers: 快照的订阅通道
+#[derive(Default)]
+pub(super) struct Inspector {
+    snapshots: HashMap<String, StateSnapshot>,
+    subscribers: Vec<Channel<StateSnapshot>>,
+}
+
+impl Inspector {
+    /// 从客户端的同步消息和回想记录上报中提取运行时状态，合并到该客户端的快照并推送给订阅者
+    /// 回想记录只更新已有的快照，其他消息不做任何操作
+    pub(super) fn observe(&mut self, client_id: &str, payload: &ClientPayload) {
+        let snapshot = match payload {
+            ClientPayload::Debug(message) => {
+                let Some((scene, stage)) = message.client_sync() else {
+                    return;
+                };
+                let snapshot = self
+                    .snapshots
+                    .entry(client_id.to_string())
+                    .or_insert_with(|| StateSnapshot::new(client_id));
+                snapshot.merge(scene, stage);
+                snapshot
+            }
+            ClientPayload::Bridge(BridgeReport::Backlog {
+                entries,
+            }) => {
+                let Some(snapshot) = self.snapshots.get_mut(client_id) else {
+                    return;
+                };
+                snapshot.backlog = entries.clone();
+                snapshot.updated_at = now_millis();
+                snapshot
+            }
+            _ => return,
+        };
+
+        let snapshot = snapshot.clone();
+        self.subscribers
+            .retain(|channel| channel.send(snapshot.clone()).is_ok());
+    }
+
+    /// 移除已断开客户端的快照
+    pub(super) fn remove_client(&mut self, client_id: &str) {
+        self.snapshots.remove(client_id);
+    }
+}
+
+/// 校验变量名
+/// 变量名会拼接进 setVar 指令，不能包含指令分隔符、运算符或空白
+fn validate_variable_name(name: &str) -> AppResult<()> {
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '_' | '$' | '.'));
+    if !valid {
+        return Err(AppError::Server(format!("无效的变量名: {name}")));
+    }
+    Ok(())
+}
+
+/// 获取客户端的运行时状态快照
+///
+/// 参数：
+/// - state: 服务器状态
+/// - client_id: 客户端ID
+///
+/// 返回：
+/// - 成功：最新快照，客户端尚未上报状态时为 None
+/// - 失败：错误信息
+#[tauri::command]
+pub async fn get_state_snapshot(
+    state: TauriState<'_, Mutex<ServerState>>,
+    client_id: String,
+) -> AppResult<Option<StateSnapshot>> {
+    let state_guard = state.lock().await;
+    let inspector = state_guard.app_state.inspector.lock().await;
+    Ok(inspector.snapshots.get(&client_id).cloned())
+}
+
+/// 订阅运行时状态快照
+/// 之后任一客户端的状态变化都会通过该通道推送完整快照，不再需要时调用 unsubscribe_state_snapshots 退订
+///
+/// 参数：
+/// - state: 服务器状态
+/// - on_snapshot: 快照推送通道
+///
+/// 返回：
+/// - 成功：空值
+/// - 失败：错误信息
+#[tauri::command]
+pub async fn subscribe_state_snapshots(
+    state: TauriState<'_, Mutex<ServerState>>,
+    on_snapshot: Channel<StateSnapshot>,
+) -> AppResult<()> {
+    let state_guard = state.lock().await;
+    state_guard
+        .app_state
+        .inspector
+        .lock()
+        .await
+        .subscribers
+        .push(on_snapshot);
+
+    Ok(())
+}
+
+/// 退订运行时状态快照
+///
+/// 参数：
+/// - state: 服务器状态
+/// - channel_id: 订阅时传入的推送通道ID
+///
+/// 返回：
+/// - 成功：空值，通道未订阅时同样成功
+/// - 失败：错误信息
+#[tauri::command]
+pub async fn unsubscribe_state_snapshots(
+    state: TauriState<'_, Mutex<ServerState>>,
+    channel_id: u32,
+) -> AppResult<()> {
+    let state_guard = state.lock().await;
+    state_guard
+        .app_state
+        .inspector
+        .lock()
+        .await
+        .subscribers
+        .retain(|channel| channel.id() != channel_id);
+
+    Ok(())
+}
+
+/// 设置预览中的变量
+/// 向客户端发送 `setVar:{name}={value};` 执行指令，新的变量值随下一次状态上报返回
+///
+/// 参数：
+/// - state: 服务器状态
+/// - client_id: 目标客户端ID
+/// - name: 变量名
+/// - value: WebGAL 表达式形式的变量值，如 `10`、`true`、`a+1`
+///
+/// 返回：
+/// - 成功：空值
+/// - 失败：错误信息
+#[tauri::command]
+pub async fn set_variable(
+    state: TauriState<'_, Mutex<ServerState>>,
+    client_id: String,
+    name: String,
+    value: String,
+) -> AppResult<()> {
+    validate_variable_name(&name)?;
+    if value.is_empty() || value.contains([';', '\n', '\r']) {
+        return Err(AppError::Server(format!("无效的变量值: {value}")));
+    }
+
+    let message = DebugMessage::execute_command(format!("setVar:{name}={value};"));
+
+    let state_guard = state.lock().await;
+    state_guard
+        .app_state
+        .send_to_client(&client_id, &message)
+        .await
+}
@@ -0,0 +1,460 @@
+// 局域网预览模块：让手机、平板等设备通过局域网访问预览
+// 主要功能：
+// 1. 访问控制：为每个预览会话签发随机令牌，非本机请求必须携带令牌
+// 2. 地址探测：获取本机局域网地址，生成可分享的预览链接
+
+use std::{
+    collections::HashMap,
+    net::{IpAddr, SocketAddr, UdpSocket},
+    sync::Arc,
+};
+
+use axum::{
+    extract::{ConnectInfo, Request, State as AxumState},
+    http::{
+        header::{COOKIE, SET_COOKIE},
+        HeaderMap, HeaderValue, Method, StatusCode,
+    },
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use serde::Serialize;
+use tauri::State as TauriState;
+use tokio::sync::Mutex;
+
+use super::{now_millis, random_id, AppState, ServerState};
+use crate::commands::{AppError, AppResult};
+
+/// 访问令牌的查询参数名
+const TOKEN_QUERY_KEY: &str = "token";
+
+/// 访问令牌的 Cookie 名前缀，完整名称为前缀加站点哈希
+const TOKEN_COOKIE_PREFIX: &str = "webgal_craft_token_";
+
+/// 存档接口的路径前缀，其后为站点哈希
+const SAVES_API_PREFIX: &str = "/api/saves/";
+
+/// 访问令牌的随机字节数
+const TOKEN_BYTES: usize = 16;
+
+/// 预览会话
+/// 包含：
+/// - hash: 会话允许访问的站点哈希
+/// - created_at: 会话创建时间（Unix 毫秒）
+struct PreviewSession {
+    hash: String,
+    created_at: u64,
+}
+
+/// 访问控制状态
+/// 包含：
+/// - enabled: 是否要求非本机请求携带令牌，服务器绑定到非回环地址时启用
+/// - sessions: 令牌到预览会话的映射表
+#[derive(Default)]
+pub(super) struct AccessControl {
+    pub(super) enabled: bool,
+    sessions: HashMap<String, PreviewSession>,
+}
+
+/// 请求的令牌校验结果
+#[derive(Debug, PartialEq, Eq)]
+enum TokenCheck {
+    /// 缺少令牌或令牌无效
+    Denied,
+    /// 通过 Cookie 中的令牌校验
+    Cookie,
+    /// 通过查询参数中的令牌校验，响应时需写入 Cookie
+    Query(String),
+}
+
+impl AccessControl {
+    /// 校验请求携带的令牌，优先使用查询参数中的令牌，其次是 Cookie
+    ///
+    /// 参数：
+    /// - hash: 请求路径中的站点哈希，非站点请求时为 None
+    /// - query: 请求的查询字符串
+    /// - headers: 请求头
+    ///
+    /// 返回：校验结果
+    fn check_request(
+        &self,
+        hash: Option<&str>,
+        query: Option<&str>,
+        headers: &HeaderMap,
+    ) -> TokenCheck {
+        if let Some(token) = query_token(query).filter(|token| self.authorize(token, hash)) {
+            return TokenCheck::Query(token);
+        }
+
+        let authorized = cookie_tokens(headers).any(|(cookie_hash, token)| match hash {
+            Some(hash) => cookie_hash == hash && self.authorize(token, Some(hash)),
+            None => self.authorize(token, None),
+        });
+        match authorized {
+            true => TokenCheck::Cookie,
+            false => TokenCheck::Denied,
+        }
+    }
+
+    /// 校验令牌，指定站点哈希时要求令牌属于该站点
+    fn authorize(&self, token: &str, hash: Option<&str>) -> bool {
+        self.sessions
+            .get(token)
+            .is_some_and(|session| hash.is_none_or(|hash| hash == session.hash))
+    }
+
+    /// 撤销指定站点的全部预览会话
+    pub(super) fn revoke_site(&mut self, hash: &str) {
+        self.sessions.retain(|_, session| session.hash != hash);
+    }
+}
+
+/// 预览会话信息，返回给前端用于分享
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PreviewSessionInfo {
+    token: String,
+    hash: String,
+    url: String,
+    created_at: u64,
+}
+
+/// 访问令牌校验中间件
+/// 本机请求和 CORS 预检请求直接放行；局域网请求需在查询参数或 Cookie 中携带有效令牌。
+/// 通过查询参数校验成功后写入 Cookie，使页面内的相对路径资源请求和
+/// WebSocket 连接无需再携带查询参数。
+pub(super) async fn require_token(
+    AxumState(state): AxumState<Arc<AppState>>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let access = state.access.read().await;
+
+    // 浏览器发送 CORS 预检请求时不携带 Cookie，预检响应只包含响应头，无需令牌
+    if !access.enabled
+        || addr.ip().to_canonical().is_loopback()
+        || request.method() == Method::OPTIONS
+    {
+        drop(access);
+        return next.run(request).await;
+    }
+
+    let hash = token_site_hash(request.uri().path()).map(str::to_owned);
+    let check = access.check_request(hash.as_deref(), request.uri().query(), request.headers());
+    drop(access);
+
+    if check == TokenCheck::Denied {
+        return (StatusCode::UNAUTHORIZED, "缺少或无效的访问令牌").into_response();
+    }
+
+    let mut response = next.run(request).await;
+
+    if let (Some(hash), TokenCheck::Query(token)) = (hash, check) {
+        let cookie = format!("{TOKEN_COOKIE_PREFIX}{hash}={token}; Path=/; HttpOnly; SameSite=Lax");
+        if let Ok(value) = HeaderValue::from_str(&cookie) {
+            response.headers_mut().append(SET_COOKIE, value);
+        }
+    }
+
+    response
+}
+
+/// 从请求路径中提取站点哈希
+/// 例如：/game/abc/index.html -> abc
+pub(super) fn site_hash_from_path(path: &str) -> Option<&str> {
+    path.strip_prefix("/game/")?
+        .split('/')
+        .next()
+        .filter(|hash| !hash.is_empty())
+}
+
+/// 获取令牌校验时请求所属的站点哈希
+/// 站点文件（/game/{hash}/...）与存档接口（/api/saves/{hash}/...）只接受该站点的令牌，
+/// 其他请求（如 /api/webgalsync）接受任一站点的令牌
+fn token_site_hash(path: &str) -> Option<&str> {
+    site_hash_from_path(path).or_else(|| {
+        path.strip_prefix(SAVES_API_PREFIX)?
+            .split('/')
+            .next()
+            .filter(|hash| !hash.is_empty())
+    })
+}
+
+/// 从查询字符串中提取访问令牌
+fn query_token(query: Option<&str>) -> Option<String> {
+    query?
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(key, _)| *key == TOKEN_QUERY_KEY)
+        .map(|(_, value)| value.to_string())
+}
+
+/// 从 Cookie 中提取所有访问令牌
+/// 返回 (站点哈希, 令牌) 迭代器
+fn cookie_tokens(headers: &HeaderMap) -> impl Iterator<Item = (&str, &str)> {
+    headers
+        .get_all(COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(';'))
+        .filter_map(|pair| pair.trim().split_once('='))
+        .filter_map(|(name, token)| Some((name.strip_prefix(TOKEN_COOKIE_PREFIX)?, token)))
+}
+
+/// 探测本机的局域网 IPv4 地址
+/// 通过 UDP 套接字的 connect 让系统选择出口网卡，不会真正发送数据
+fn detect_lan_ip() -> Option<IpAddr> {
+    let socket = UdpSocket::bind("0.0.0.0:0").ok()?;
+    socket.connect("8.8.8.8:80").ok()?;
+    socket
+        .local_addr()
+        .ok()
+        .map(|addr| addr.ip())
+        .filter(|ip| !ip.is_loopback() && !ip.is_unspecified())
+}
+
+/// 获取可分享给局域网设备的地址
+/// - 绑定到回环地址：局域网设备无法访问，返回 None
+/// - 绑定到所有网卡：使用探测到的局域网地址
+/// - 绑定到指定网卡：直接使用该地址
+fn shareable_ip(bound: IpAddr) -> Option<IpAddr> {
+    if bound.is_loopback() {
+        None
+    } else if bound.is_unspecified() {
+        detect_lan_ip()
+    } else {
+        Some(bound)
+    }
+}
+
+/// 获取服务器对局域网设备的访问地址
+fn shareable_addr(state: &ServerState) -> AppResult<SocketAddr> {
+    let addr = state
+        .server_address()
+        .ok_or_else(|| AppError::Server("服务器未启动".into()))?;
+    let ip = shareable_ip(addr.ip())
+        .ok_or_else(|| AppError::Server("服务器未绑定到局域网地址".into()))?;
+    Ok(SocketAddr::new(ip, addr.port()))
+}
+
+/// 生成携带访问令牌的预览链接
+fn preview_url(addr: SocketAddr, hash: &str, token: &str) -> String {
+    format!("http://{addr}/game/{hash}/?{TOKEN_QUERY_KEY}={token}")
+}
+
+/// 获取本机局域网地址
+/// 供前端选择要绑定的网卡，探测失败时返回 None
+#[tauri::command]
+pub async fn get_lan_address() -> AppResult<Option<String>> {
+    Ok(detect_lan_ip().map(|ip| ip.to_string()))
+}
+
+/// 创建预览会话
+/// 为已注册的静态站点签发访问令牌，并返回局域网设备可直接打开的预览链接
+///
+/// 参数：
+/// - state: 服务器状态
+/// - path: 静态站点目录路径
+///
+/// 返回：
+/// - 成功：预览会话信息
+/// - 失败：错误信息
+#[tauri::command]
+pub async fn create_preview_session(
+    state: TauriState<'_, Mutex<ServerState>>,
+    path: String,
+) -> AppResult<PreviewSessionInfo> {
+    let state_guard = state.lock().await;
+
+    let share_addr = shareable_addr(&state_guard)?;
+
+    let (hash, _) = state_guard.resolve_site(&path)?;
+    if !state_guard.app_state.sites.read().await.contains_key(&hash) {
+        return Err(AppError::Server("站点未注册".into()));
+    }
+
+    let token = random_id(TOKEN_BYTES)?;
+    let created_at = now_millis();
+
+    state_guard.app_state.access.write().await.sessions.insert(
+        token.clone(),
+        PreviewSession {
+            hash: hash.clone(),
+            created_at,
+        },
+    );
+
+    Ok(PreviewSessionInfo {
+        url: preview_url(share_addr, &hash, &token),
+        token,
+        hash,
+        created_at,
+    })
+}
+
+/// 获取预览会话列表
+/// 返回当前所有有效的预览会话及其分享链接
+///
+/// 参数：
+/// - state: 服务器状态
+///
+/// 返回：
+/// - 成功：预览会话信息列表
+/// - 失败：错误信息
+#[tauri::command]
+pub async fn list_preview_sessions(
+    state: TauriState<'_, Mutex<ServerState>>,
+) -> AppResult<Vec<PreviewSessionInfo>> {
+    let state_guard = state.lock().await;
+    let share_addr = shareable_addr(&state_guard)?;
+    let access = state_guard.app_state.access.read().await;
+
+    let mut sessions: Vec<_> = access
+        .sessions
+        .iter()
+        .map(|(token, session)| PreviewSessionInfo {
+            token: token.clone(),
+            hash: session.hash.clone(),
+            url: preview_url(share_addr, &session.hash, token),
+            created_at: session.created_at,
+        })
+        .collect();
+    sessions.sort_by_key(|session| session.created_at);
+
+    Ok(sessions)
+}
+
+/// 撤销预览会话
+/// 令牌失效后，持有该令牌的设备将无法继续访问
+///
+/// 参数：
+/// - state: 服务器状态
+/// - token: 访问令牌
+///
+/// 返回：
+/// - 成功：空值
+/// - 失败：错误信息
+#[tauri::command]
+pub async fn revoke_preview_session(
+    state: TauriState<'_, Mutex<ServerState>>,
+    token: String,
+) -> AppResult<()> {
+    let state_guard = state.lock().await;
+    state_guard
+        .app_state
+        .access
+        .write()
+        .await
+        .sessions
+        .remove(&token);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::{header::COOKIE, HeaderMap, HeaderValue};
+
+    use super::{token_site_hash, AccessControl, PreviewSession, TokenCheck, TOKEN_COOKIE_PREFIX};
+
+    const SITE: &str = "abc";
+    const TOKEN: &str = "0123456789abcdef";
+
+    fn access_control() -> AccessControl {
+        let mut access = AccessControl {
+            enabled: true,
+            ..Default::default()
+        };
+        access.sessions.insert(
+            TOKEN.to_string(),
+            PreviewSession {
+                hash: SITE.to_string(),
+                created_at: 0,
+            },
+        );
+        access
+    }
+
+    fn cookie(hash: &str, token: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        let value = format!("theme=dark; {TOKEN_COOKIE_PREFIX}{hash}={token}");
+        headers.insert(COOKIE, HeaderValue::from_str(&value).unwrap());
+        headers
+    }
+
+    #[test]
+    fn denies_missing_token() {
+        let access = access_control();
+        let check = access.check_request(Some(SITE), None, &HeaderMap::new());
+        assert_eq!(check, TokenCheck::Denied);
+        let check = access.check_request(Some(SITE), Some("lang=zh"), &HeaderMap::new());
+        assert_eq!(check, TokenCheck::Denied);
+    }
+
+    #[test]
+    fn denies_wrong_token() {
+        let access = access_control();
+        let check = access.check_request(Some(SITE), Some("token=wrong"), &cookie(SITE, "wrong"));
+        assert_eq!(check, TokenCheck::Denied);
+    }
+
+    #[test]
+    fn denies_token_of_another_site() {
+        let access = access_control();
+        let query = format!("token={TOKEN}");
+        let check = access.check_request(Some("other"), Some(&query), &HeaderMap::new());
+        assert_eq!(check, TokenCheck::Denied);
+        let check = access.check_request(Some("other"), None, &cookie(SITE, TOKEN));
+        assert_eq!(check, TokenCheck::Denied);
+    }
+
+    #[test]
+    fn accepts_query_token_and_prefers_it_over_cookie() {
+        let access = access_control();
+        let query = format!("lang=zh&token={TOKEN}");
+        let expected = TokenCheck::Query(TOKEN.to_string());
+        let check = access.check_request(Some(SITE), Some(&query), &HeaderMap::new());
+        assert_eq!(check, expected);
+        let check = access.check_request(Some(SITE), Some(&query), &cookie(SITE, TOKEN));
+        assert_eq!(check, expected);
+    }
+
+    #[test]
+    fn accepts_cookie_token() {
+        let access = access_control();
+        let check = access.check_request(Some(SITE), None, &cookie(SITE, TOKEN));
+        assert_eq!(check, TokenCheck::Cookie);
+        // 非站点请求（如 /api/webgalsync）接受任一站点的令牌
+        let check = access.check_request(None, None, &cookie(SITE, TOKEN));
+        assert_eq!(check, TokenCheck::Cookie);
+        // Cookie 名中的站点哈希必须与请求的站点一致
+        let check = access.check_request(Some(SITE), None, &cookie("other", TOKEN));
+        assert_eq!(check, TokenCheck::Denied);
+    }
+
+    #[test]
+    fn scopes_site_and_saves_requests_to_their_site() {
+        assert_eq!(token_site_hash("/game/abc/index.html"), Some("abc"));
+        assert_eq!(token_site_hash("/api/saves/abc"), Some("abc"));
+        assert_eq!(token_site_hash("/api/saves/abc/WebGAL-saves"), Some("abc"));
+        assert_eq!(token_site_hash("/api/webgalsync"), None);
+        assert_eq!(token_site_hash("/api/saves/"), None);
+
+        // 其他站点的令牌不能访问该站点的存档接口
+        let access = access_control();
+        let query = format!("token={TOKEN}");
+        let hash = token_site_hash("/api/saves/other/WebGAL-saves");
+        let check = access.check_request(hash, Some(&query), &cookie(SITE, TOKEN));
+        assert_eq!(check, TokenCheck::Denied);
+    }
+
+    #[test]
+    fn denies_revoked_session() {
+        let mut access = access_control();
+        access.revoke_site(SITE);
+        let query = format!("token={TOKEN}");
+        let check = access.check_request(Some(SITE), Some(&query), &cookie(SITE, TOKEN));
+        assert_eq!(check, TokenCheck::Denied);
+    }
+}
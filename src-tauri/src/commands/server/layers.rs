@@ -0,0 +1,79 @@
+// 分层目录模块：从多个目录中组合出一个站点，使游戏无需复制引擎即可预览
+// 主要功能：
+// 1. 分层查找：按顺序在站点目录和附加目录中查找文件，使用第一个存在的文件
+// 2. 引擎链接：游戏目录在前、共享引擎目录在后，游戏只需包含自己的文件
+// 3. 引擎切换：重新挂载时替换附加目录，即可使用其他版本的引擎预览
+
+use std::path::{Path, PathBuf};
+
+use axum::{
+    http::{Request, StatusCode},
+    response::{IntoResponse, Response},
+};
+use tower::util::ServiceExt;
+use tower_http::services::ServeDir;
+
+use super::normalize_path;
+use crate::commands::AppResult;
+
+/// 分层服务目录
+/// 第一层为站点目录，其后为附加目录
+#[derive(Clone)]
+pub(super) struct LayeredServeDir {
+    serve_dirs: Vec<ServeDir>,
+}
+
+impl LayeredServeDir {
+    /// 创建分层服务目录
+    ///
+    /// 参数：
+    /// - root: 站点目录
+    /// - layers: 依次查找的附加目录
+    pub(super) fn new(root: &Path, layers: &[PathBuf]) -> Self {
+        let serve_dirs = std::iter::once(root)
+            .chain(layers.iter().map(PathBuf::as_path))
+            .map(|dir| ServeDir::new(dir).append_index_html_on_directories(true))
+            .collect();
+
+        Self {
+            serve_dirs,
+        }
+    }
+
+    /// 按顺序在各层中查找并返回文件
+    /// 某一层返回 404 时继续查找下一层，所有层都不存在时返回最后一层的响应
+    pub(super) async fn serve(&self, request: Request<()>) -> Response {
+        let (parts, ()) = request.into_parts();
+        let mut last = None;
+
+        for serve_dir in &self.serve_dirs {
+            let mut layer_request = Request::new(());
+            *layer_request.method_mut() = parts.method.clone();
+            *layer_request.uri_mut() = parts.uri.clone();
+            *layer_request.headers_mut() = parts.headers.clone();
+
+            let response = match serve_dir.clone().oneshot(layer_request).await {
+                Ok(response) => response.into_response(),
+                Err(infallible) => match infallible {},
+            };
+            if response.status() != StatusCode::NOT_FOUND {
+                return response;
+            }
+            last = Some(response);
+        }
+
+        last.unwrap_or_else(|| StatusCode::NOT_FOUND.into_response())
+    }
+}
+
+/// 校验并标准化附加目录
+///
+/// 参数：
+/// - layers: 附加目录路径列表
+///
+/// 返回：
+/// - 成功：标准化后的路径列表，顺序不变
+/// - 失败：错误信息（任一目录不存在或不是目录）
+pub(super) fn normalize_layers(layers: &[String]) -> AppResult<Vec<PathBuf>> {
+    layers.iter().map(|layer| normalize_path(layer)).collect()
+}
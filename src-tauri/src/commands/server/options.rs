@@ -0,0 +1,177 @@
+// 站点选项模块：按站点配置响应头与压缩，使预览行为接近实际部署环境
+// 主要功能：
+// 1. 响应压缩：可选的 gzip / brotli 压缩
+// 2. 跨源隔离：COOP/COEP 响应头，供需要 SharedArrayBuffer 的插件使用
+// 3. 跨域访问：为外部工具开启 CORS，并响应预检请求
+// 4. 缓存模式：禁止缓存、每次协商或模拟生产环境
+// 5. 编辑器桥接：向站点首页注入桥接脚本（见 bridge 子模块）
+// 6. 存档同步：将预览存档保存到项目目录（见 saves 子模块）
+
+use axum::{
+    http::{
+        header::{
+            ACCESS_CONTROL_ALLOW_HEADERS, ACCESS_CONTROL_ALLOW_METHODS,
+            ACCESS_CONTROL_ALLOW_ORIGIN, ACCESS_CONTROL_MAX_AGE, ACCESS_CONTROL_REQUEST_HEADERS,
+            CACHE_CONTROL, VARY,
+        },
+        HeaderMap, HeaderName, HeaderValue, StatusCode,
+    },
+    response::{IntoResponse, Response},
+};
+use serde::{Deserialize, Serialize};
+
+/// 禁止缓存的 Cache-Control 值，服务器的默认行为
+pub(super) const NO_STORE: &str = "no-store, no-cache, must-revalidate, max-age=0";
+
+/// 模拟生产环境时的 Cache-Control 值，与常见静态托管服务的默认值一致
+const PRODUCTION_CACHE_CONTROL: &str = "public, max-age=600";
+
+/// 预检响应允许的请求方法，站点只提供静态文件
+const CORS_ALLOW_METHODS: &str = "GET, HEAD, OPTIONS";
+
+/// 预检响应的缓存时间（秒）
+const CORS_MAX_AGE: &str = "600";
+
+/// 缓存模式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CacheMode {
+    /// 禁止缓存，每次都重新加载
+    #[default]
+    NoStore,
+    /// 允许缓存，但每次使用前都向服务器协商
+    Revalidate,
+    /// 模拟生产环境，缓存一段时间内直接使用
+    Production,
+}
+
+impl CacheMode {
+    fn header_value(self) -> HeaderValue {
+        HeaderValue::from_static(match self {
+            Self::NoStore => NO_STORE,
+            Self::Revalidate => "no-cache",
+            Self::Production => PRODUCTION_CACHE_CONTROL,
+        })
+    }
+}
+
+/// 站点选项
+/// 包含：
+/// - gzip: 是否启用 gzip 压缩
+/// - brotli: 是否启用 brotli 压缩
+/// - cross_origin_isolation: 是否发送 COOP/COEP 响应头
+/// - cors: 是否允许任意来源跨域访问
+/// - cache: 缓存模式
+/// - bridge: 是否向站点首页注入编辑器桥接脚本
+/// - persist_saves: 是否将预览存档同步到项目的 .webgal-craft/saves/ 目录
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SiteOptions {
+    pub(super) gzip: bool,
+    pub(super) brotli: bool,
+    cross_origin_isolation: bool,
+    cors: bool,
+    cache: CacheMode,
+    pub(super) bridge: bool,
+    pub(super) persist_saves: bool,
+}
+
+impl SiteOptions {
+    /// 为站点响应添加配置的响应头
+    pub(super) fn apply_headers(&self, response: &mut Response) {
+        let headers = response.headers_mut();
+
+        headers.insert(CACHE_CONTROL, self.cache.header_value());
+
+        if self.cross_origin_isolation {
+            headers.insert(
+                HeaderName::from_static("cross-origin-opener-policy"),
+                HeaderValue::from_static("same-origin"),
+            );
+            headers.insert(
+                HeaderName::from_static("cross-origin-embedder-policy"),
+                HeaderValue::from_static("require-corp"),
+            );
+        }
+
+        if self.cors {
+            headers.insert(ACCESS_CONTROL_ALLOW_ORIGIN, HeaderValue::from_static("*"));
+        }
+    }
+
+    /// 生成 CORS 预检请求的响应
+    /// 允许预检请求中声明的全部请求头，未开启跨域访问时返回 None
+    ///
+    /// 参数：
+    /// - request_headers: 预检请求的请求头
+    ///
+    /// 返回：预检响应
+    pub(super) fn preflight_response(&self, request_headers: &HeaderMap) -> Option<Response> {
+        if !self.cors {
+            return None;
+        }
+
+        let mut response = StatusCode::NO_CONTENT.into_response();
+        let headers = response.headers_mut();
+        headers.insert(ACCESS_CONTROL_ALLOW_ORIGIN, HeaderValue::from_static("*"));
+        headers.insert(
+            ACCESS_CONTROL_ALLOW_METHODS,
+            HeaderValue::from_static(CORS_ALLOW_METHODS),
+        );
+        if let Some(requested) = request_headers.get(ACCESS_CONTROL_REQUEST_HEADERS) {
+            headers.insert(ACCESS_CONTROL_ALLOW_HEADERS, requested.clone());
+            headers.insert(
+                VARY,
+                HeaderValue::from_static("Access-Control-Request-Headers"),
+            );
+        }
+        headers.insert(
+            ACCESS_CONTROL_MAX_AGE,
+            HeaderValue::from_static(CORS_MAX_AGE),
+        );
+        Some(response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::{
+        header::{
+            ACCESS_CONTROL_ALLOW_HEADERS, ACCESS_CONTROL_ALLOW_METHODS,
+            ACCESS_CONTROL_ALLOW_ORIGIN, ACCESS_CONTROL_REQUEST_HEADERS,
+        },
+        HeaderMap, HeaderValue, StatusCode,
+    };
+
+    use super::SiteOptions;
+
+    #[test]
+    fn preflight_requires_cors() {
+        assert!(SiteOptions::default()
+            .preflight_response(&HeaderMap::new())
+            .is_none());
+    }
+
+    #[test]
+    fn preflight_allows_requested_headers() {
+        let options = SiteOptions {
+            cors: true,
+            ..Default::default()
+        };
+        let mut request_headers = HeaderMap::new();
+        request_headers.insert(
+            ACCESS_CONTROL_REQUEST_HEADERS,
+            HeaderValue::from_static("content-type, x-custom"),
+        );
+
+        let response = options.preflight_response(&request_headers).unwrap();
+        let headers = response.headers();
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        assert_eq!(headers[ACCESS_CONTROL_ALLOW_ORIGIN], "*");
+        assert_eq!(headers[ACCESS_CONTROL_ALLOW_METHODS], "GET, HEAD, OPTIONS");
+        assert_eq!(
+            headers[ACCESS_CONTROL_ALLOW_HEADERS],
+            "content-type, x-custom"
+        );
+    }
+}
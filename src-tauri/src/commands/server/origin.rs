@@ -0,0 +1,99 @@
+// 源隔离模块：为每个预览站点提供独立的浏览器源
+// 主要功能：
+// 1. 子域名路由：将 `{站点ID}.localhost:{端口}` 的请求映射到对应站点
+// 2. 预览地址：生成基于子域名的预览地址，使各游戏的 localStorage、IndexedDB 互不干扰
+
+use std::net::SocketAddr;
+
+use axum::{
+    extract::Request,
+    http::{header::HOST, HeaderMap, Uri},
+    middleware::Next,
+    response::Response,
+};
+use tauri::State as TauriState;
+use tokio::sync::Mutex;
+
+use super::ServerState;
+use crate::commands::{AppError, AppResult};
+
+/// 站点子域名的顶级域名
+const SITE_HOST_SUFFIX: &str = ".localhost";
+
+/// 子域名下保留给服务器自身的路径前缀，不映射到站点文件
+const RESERVED_PATH_PREFIX: &str = "/api/";
+
+/// 从 Host 中提取站点ID
+/// 例如：abc.localhost:8899 -> abc
+pub(super) fn site_from_host(headers: &HeaderMap) -> Option<&str> {
+    let host = headers.get(HOST)?.to_str().ok()?;
+    let hostname = host.rsplit_once(':').map_or(host, |(hostname, _)| hostname);
+
+    hostname
+        .strip_suffix(SITE_HOST_SUFFIX)
+        .filter(|id| !id.is_empty() && !id.contains('.'))
+}
+
+/// 子域名路由中间件
+/// 将 `{站点ID}.localhost` 下的请求路径改写为 `/game/{站点ID}/...`，
+/// 复用路径路由的站点查找、访问控制与静态文件服务。
+/// `/api/` 下的请求（如 WebSocket）保持不变，由处理函数根据 Host 识别站点。
+pub(super) async fn route_by_host(mut request: Request, next: Next) -> Response {
+    let Some(id) = site_from_host(request.headers()).map(str::to_owned) else {
+        return next.run(request).await;
+    };
+
+    let uri = request.uri();
+    if !uri.path().starts_with(RESERVED_PATH_PREFIX) {
+        let target = match uri.query() {
+            Some(query) => format!("/game/{id}{}?{query}", uri.path()),
+            None => format!("/game/{id}{}", uri.path()),
+        };
+        if let Ok(uri) = target.parse::<Uri>() {
+            *request.uri_mut() = uri;
+        }
+    }
+
+    next.run(request).await
+}
+
+/// 生成站点的预览地址
+/// - 绑定到回环地址或所有网卡：使用 `{站点ID}.localhost`，每个站点拥有独立的源
+/// - 绑定到指定网卡：子域名无法解析到该地址，退回到 `/game/{站点ID}/` 路径形式
+pub(super) fn site_url(addr: SocketAddr, id: &str) -> String {
+    let ip = addr.ip().to_canonical();
+    if ip.is_loopback() || ip.is_unspecified() {
+        format!("http://{id}{SITE_HOST_SUFFIX}:{}/", addr.port())
+    } else {
+        format!("http://{addr}/game/{id}/")
+    }
+}
+
+/// 获取站点的预览地址
+/// 站点需已通过 add_static_site 添加
+///
+/// 参数：
+/// - state: 服务器状态
+/// - path: 静态站点目录路径
+///
+/// 返回：
+/// - 成功：预览地址
+/// - 失败：错误信息
+#[tauri::command]
+pub async fn get_preview_url(
+    state: TauriState<'_, Mutex<ServerState>>,
+    path: String,
+) -> AppResult<String> {
+    let state_guard = state.lock().await;
+
+    let addr = state_guard
+        .server_address()
+        .ok_or_else(|| AppError::Server("服务器未启动".into()))?;
+
+    let (id, _) = state_guard.resolve_site(&path)?;
+    if !state_guard.app_state.sites.read().await.contains_key(&id) {
+        return Err(AppError::Server("站点未注册".into()));
+    }
+
+    Ok(site_url(addr, &id))
+}
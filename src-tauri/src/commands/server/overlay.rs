@@ -0,0 +1,186 @@
+// 内存覆盖层模块：让预览服务器提供编辑器中尚未保存的文件内容
+// 主要功能：
+// 1. 虚拟文件：按站点保存以相对路径为键的文件内容
+// 2. 优先提供：请求的文件存在于覆盖层时，代替磁盘文件返回
+// 3. 实时刷新：覆盖层变化时通知正在预览该站点的客户端
+
+use std::collections::HashMap;
+
+use axum::{
+    http::{header::CONTENT_TYPE, HeaderValue},
+    response::{IntoResponse, Response},
+};
+use tauri::State as TauriState;
+use tokio::sync::Mutex;
+
+use super::{watcher, ServerState};
+use crate::commands::{AppError, AppResult};
+
+/// 站点覆盖层映射表，键为站点ID，值为(相对路径, 文件内容)映射表
+pub(super) type Overlays = HashMap<String, HashMap<String, String>>;
+
+/// 标准化覆盖层文件的相对路径
+/// 统一使用 `/` 分隔并去除首尾的 `/`，目录请求指向其中的 index.html。
+/// 包含 `.` 或 `..` 路径段时返回 None
+pub(super) fn overlay_key(path: &str) -> Option<String> {
+    let path = path.replace('\\', "/");
+    let trimmed = path.trim_start_matches('/');
+
+    let mut segments: Vec<_> = trimmed.split('/').filter(|s| !s.is_empty()).collect();
+    if segments
+        .iter()
+        .any(|segment| matches!(*segment, "." | ".."))
+    {
+        return None;
+    }
+    if segments.is_empty() || trimmed.ends_with('/') {
+        segments.push("index.html");
+    }
+
+    Some(segments.join("/"))
+}
+
+/// 将覆盖层文件内容转换为响应，根据扩展名推断 Content-Type
+pub(super) fn overlay_response(key: &str, content: String) -> Response {
+    let mime = mime_guess::from_path(key).first_or_text_plain();
+    let mut response = content.into_response();
+    if let Ok(value) = HeaderValue::from_str(mime.essence_str()) {
+        response.headers_mut().insert(CONTENT_TYPE, value);
+    }
+    response
+}
+
+/// 写入覆盖层文件并通知预览客户端
+///
+/// 参数：
+/// - state: 服务器状态
+/// - path: 静态站点目录路径
+/// - file: 相对于站点根目录的文件路径
+/// - content: 文件内容
+/// - create: 文件不在覆盖层中时是否创建
+async fn write_overlay_file(
+    state: &ServerState,
+    path: &str,
+    file: &str,
+    content: String,
+    create: bool,
+) -> AppResult<()> {
+    let (hash, _) = state.resolve_site(path)?;
+    let key =
+        overlay_key(file).ok_or_else(|| AppError::Server(format!("无效的文件路径: {file}")))?;
+
+    let app_state = &state.app_state;
+    if !app_state.sites.read().await.contains_key(&hash) {
+        return Err(AppError::Server("站点未注册".into()));
+    }
+
+    {
+        let mut overlays = app_state.overlays.write().await;
+        let exists = overlays
+            .get(&hash)
+            .is_some_and(|files| files.contains_key(&key));
+        if !create && !exists {
+            return Err(AppError::Server(format!("覆盖层中不存在该文件: {key}")));
+        }
+        overlays
+            .entry(hash.clone())
+            .or_default()
+            .insert(key.clone(), content);
+    }
+
+    watcher::notify_paths_changed(app_state, &hash, &[key]).await;
+
+    Ok(())
+}
+
+/// 放入覆盖层文件
+/// 之后对该文件的请求将返回此内容，而不是磁盘上的文件
+///
+/// 参数：
+/// - state: 服务器状态
+/// - path: 静态站点目录路径
+/// - file: 相对于站点根目录的文件路径，如 game/scene/start.txt
+/// - content: 文件内容
+///
+/// 返回：
+/// - 成功：空值
+/// - 失败：错误信息
+#[tauri::command]
+pub async fn put_overlay_file(
+    state: TauriState<'_, Mutex<ServerState>>,
+    path: String,
+    file: String,
+    content: String,
+) -> AppResult<()> {
+    let state_guard = state.lock().await;
+    write_overlay_file(&state_guard, &path, &file, content, true).await
+}
+
+/// 更新覆盖层文件
+/// 仅更新已放入覆盖层的文件，文件不存在时返回错误
+///
+/// 参数：
+/// - state: 服务器状态
+/// - path: 静态站点目录路径
+/// - file: 相对于站点根目录的文件路径
+/// - content: 新的文件内容
+///
+/// 返回：
+/// - 成功：空值
+/// - 失败：错误信息
+#[tauri::command]
+pub async fn update_overlay_file(
+    state: TauriState<'_, Mutex<ServerState>>,
+    path: String,
+    file: String,
+    content: String,
+) -> AppResult<()> {
+    let state_guard = state.lock().await;
+    write_overlay_file(&state_guard, &path, &file, content, false).await
+}
+
+/// 清除覆盖层文件
+/// 清除后恢复提供磁盘上的文件，通常在编辑器保存文件或放弃修改后调用
+///
+/// 参数：
+/// - state: 服务器状态
+/// - path: 静态站点目录路径
+/// - file: 相对于站点根目录的文件路径，为 None 时清除该站点的全部覆盖层文件
+///
+/// 返回：
+/// - 成功：空值
+/// - 失败：错误信息
+#[tauri::command]
+pub async fn clear_overlay_file(
+    state: TauriState<'_, Mutex<ServerState>>,
+    path: String,
+    file: Option<String>,
+) -> AppResult<()> {
+    let state_guard = state.lock().await;
+    let (hash, _) = state_guard.resolve_site(&path)?;
+    let app_state = &state_guard.app_state;
+
+    let removed: Vec<String> = {
+        let mut overlays = app_state.overlays.write().await;
+        match file {
+            Some(file) => {
+                let key = overlay_key(&file)
+                    .ok_or_else(|| AppError::Server(format!("无效的文件路径: {file}")))?;
+                let files = overlays.get_mut(&hash);
+                let removed = files.and_then(|files| files.remove_entry(&key));
+                if overlays.get(&hash).is_some_and(HashMap::is_empty) {
+                    overlays.remove(&hash);
+                }
+                removed.map(|(key, _)| key).into_iter().collect()
+            }
+            None => overlays
+                .remove(&hash)
+                .map(|files| files.into_keys().collect())
+                .unwrap_or_default(),
+        }
+    };
+
+    watcher::notify_paths_changed(app_state, &hash, &removed).await;
+
+    Ok(())
+}
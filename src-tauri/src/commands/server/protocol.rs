@@ -0,0 +1,469 @@
+// 调试协议模块：以强类型定义 WebGAL 调试协议，并校验收发的消息
+// 主要功能：
+// 1. 协议类型：跳转、同步、执行指令、临时场景、组件可见性、字体优化、效果和模板刷新
+// 2. 消息校验：反序列化时校验各指令必需的字段与消息内容
+// 3. 二进制帧：解析游戏发回的二进制数据（如截图）
+// 4. 消息分派：区分 WebGAL 调试消息与桥接脚本的上报消息，无法解析的消息连同原因原样转发
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::bridge::{BridgeReport, BRIDGE_EVENT};
+use crate::commands::{AppError, AppResult};
+
+/// WebGAL 调试指令编号，与 WebGAL 的 DebugCommand 枚举一致
+mod command {
+    pub const JUMP: u8 = 0;
+    pub const SYNC_FROM_CLIENT: u8 = 1;
+    pub const SYNC_FROM_EDITOR: u8 = 2;
+    pub const EXE_COMMAND: u8 = 3;
+    pub const REFETCH_TEMPLATE_FILES: u8 = 4;
+    pub const SET_COMPONENT_VISIBILITY: u8 = 5;
+    pub const TEMP_SCENE: u8 = 6;
+    pub const FONT_OPTIMIZATION: u8 = 7;
+    pub const SET_EFFECT: u8 = 8;
+}
+
+/// WebGAL 调试消息的事件名
+const MESSAGE_EVENT: &str = "message";
+
+/// 快速预览模式的跳转消息
+const JUMP_FAST: &str = "exp";
+
+/// 逐句同步模式的跳转消息
+const JUMP_SYNC: &str = "sync";
+
+/// 二进制帧头部长度前缀的字节数
+const BINARY_HEADER_LEN_BYTES: usize = 4;
+
+/// 场景位置
+/// - scene: 场景文件名，相对于 game/scene
+/// - sentence: 语句序号
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SceneMsg {
+    scene: String,
+    sentence: u32,
+}
+
+/// 组件可见性设置
+/// - component: WebGAL 组件名，如 showTitle、showTextBox
+/// - visibility: 是否可见
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ComponentVisibility {
+    component: String,
+    visibility: bool,
+}
+
+/// WebGAL 调试指令
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "RawDebugData", into = "RawDebugData")]
+pub enum DebugCommand {
+    /// 跳转到指定场景的指定语句，fast 为 true 时使用快速预览
+    Jump { scene_msg: SceneMsg, fast: bool },
+    /// 游戏向编辑器同步当前位置与舞台状态
+    SyncFromClient {
+        scene_msg: SceneMsg,
+        stage_sync_msg: Option<Value>,
+    },
+    /// 编辑器向游戏同步当前位置
+    SyncFromEditor { scene_msg: SceneMsg },
+    /// 执行一条 WebGAL 脚本
+    ExecuteCommand { script: String },
+    /// 重新拉取模板样式文件
+    RefetchTemplateFiles,
+    /// 设置界面组件的可见性
+    SetComponentVisibility {
+        components: Vec<ComponentVisibility>,
+    },
+    /// 以临时场景运行一段脚本
+    TempScene { script: String },
+    /// 开启或关闭字体优化
+    FontOptimization { enabled: bool },
+    /// 设置舞台效果
+    SetEffect { effect: Value },
+}
+
+/// WebGAL 调试指令的线上格式
+/// 各指令共用同一结构，message 字段的含义随指令变化
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawDebugData {
+    command: u8,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    scene_msg: Option<SceneMsg>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    message: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    stage_sync_msg: Option<Value>,
+}
+
+impl TryFrom<RawDebugData> for DebugCommand {
+    type Error = String;
+
+    fn try_from(raw: RawDebugData) -> Result<Self, Self::Error> {
+        let code = raw.command;
+        let scene_msg = || {
+            raw.scene_msg
+                .clone()
+                .ok_or_else(|| format!("调试指令 {code} 缺少 sceneMsg"))
+        };
+        let message = || {
+            raw.message
+                .clone()
+                .ok_or_else(|| format!("调试指令 {code} 缺少 message"))
+        };
+
+        Ok(match code {
+            command::JUMP => Self::Jump {
+                scene_msg: scene_msg()?,
+                fast: match raw.message.as_deref() {
+                    Some(JUMP_FAST) => true,
+                    Some(JUMP_SYNC) | None => false,
+                    Some(other) => return Err(format!("无效的跳转模式: {other}")),
+                },
+            },
+            command::SYNC_FROM_CLIENT => Self::SyncFromClient {
+                scene_msg: scene_msg()?,
+                stage_sync_msg: raw.stage_sync_msg,
+            },
+            command::SYNC_FROM_EDITOR => Self::SyncFromEditor {
+                scene_msg: scene_msg()?,
+            },
+            command::EXE_COMMAND => Self::ExecuteCommand {
+                script: message()?,
+            },
+            command::REFETCH_TEMPLATE_FILES => Self::RefetchTemplateFiles,
+            command::SET_COMPONENT_VISIBILITY => Self::SetComponentVisibility {
+                components: serde_json::from_str(&message()?)
+                    .map_err(|e| format!("无效的组件可见性设置: {e}"))?,
+            },
+            command::TEMP_SCENE => Self::TempScene {
+                script: message()?,
+            },
+            command::FONT_OPTIMIZATION => Self::FontOptimization {
+                enabled: message()?
+                    .parse()
+                    .map_err(|_| "字体优化开关必须为 true 或 false".to_string())?,
+            },
+            command::SET_EFFECT => {
+                let effect: Value = serde_json::from_str(&message()?)
+                    .map_err(|e| format!("无效的效果设置: {e}"))?;
+                if !effect.is_object() {
+                    return Err("效果设置必须为 JSON 对象".into());
+                }
+                Self::SetEffect {
+                    effect,
+                }
+            }
+            other => return Err(format!("未知的调试指令: {other}")),
+        })
+    }
+}
+
+impl From<DebugCommand> for RawDebugData {
+    fn from(value: DebugCommand) -> Self {
+        let raw = |code: u8| Self {
+            command: code,
+            scene_msg: None,
+            message: None,
+            stage_sync_msg: None,
+        };
+
+        match value {
+            DebugCommand::Jump {
+                scene_msg,
+                fast,
+            } => Self {
+                scene_msg: Some(scene_msg),
+                message: Some(if fast { JUMP_FAST } else { JUMP_SYNC }.to_string()),
+                ..raw(command::JUMP)
+            },
+            DebugCommand::SyncFromClient {
+                scene_msg,
+                stage_sync_msg,
+            } => Self {
+                scene_msg: Some(scene_msg),
+                stage_sync_msg,
+                ..raw(command::SYNC_FROM_CLIENT)
+            },
+            DebugCommand::SyncFromEditor {
+                scene_msg,
+            } => Self {
+                scene_msg: Some(scene_msg),
+                ..raw(command::SYNC_FROM_EDITOR)
+            },
+            DebugCommand::ExecuteCommand {
+                script,
+            } => Self {
+                message: Some(script),
+                ..raw(command::EXE_COMMAND)
+            },
+            DebugCommand::RefetchTemplateFiles => raw(command::REFETCH_TEMPLATE_FILES),
+            DebugCommand::SetComponentVisibility {
+                components,
+            } => Self {
+                message: serde_json::to_string(&components).ok(),
+                ..raw(command::SET_COMPONENT_VISIBILITY)
+            },
+            DebugCommand::TempScene {
+                script,
+            } => Self {
+                message: Some(script),
+                ..raw(command::TEMP_SCENE)
+            },
+            DebugCommand::FontOptimization {
+                enabled,
+            } => Self {
+                message: Some(enabled.to_string()),
+                ..raw(command::FONT_OPTIMIZATION)
+            },
+            DebugCommand::SetEffect {
+                effect,
+            } => Self {
+                message: Some(effect.to_string()),
+                ..raw(command::SET_EFFECT)
+            },
+        }
+    }
+}
+
+/// WebGAL 调试消息
+/// - event: 事件名，WebGAL 使用 message
+/// - data: 调试指令
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DebugMessage {
+    event: String,
+    data: DebugCommand,
+}
+
+impl DebugMessage {
+    /// 创建执行一条 WebGAL 脚本的调试消息
+    pub(super) fn execute_command(script: String) -> Self {
+        Self {
+            event: MESSAGE_EVENT.to_string(),
+            data: DebugCommand::ExecuteCommand {
+                script,
+            },
+        }
+    }
+
+    /// 游戏同步的当前位置与舞台状态，非 SyncFromClient 消息时为 None
+    pub(super) fn client_sync(&self) -> Option<(&SceneMsg, Option<&Value>)> {
+        match &self.data {
+            DebugCommand::SyncFromClient {
+                scene_msg,
+                stage_sync_msg,
+            } => Some((scene_msg, stage_sync_msg.as_ref())),
+            _ => None,
+        }
+    }
+
+    /// 是否为决定游戏当前位置的场景状态（场景跳转或逐句同步）
+    /// 客户端落后时重放最近一条即可恢复同步
+    pub(super) fn is_scene_state(&self) -> bool {
+        matches!(
+            self.data,
+            DebugCommand::Jump { .. } | DebugCommand::SyncFromEditor { .. }
+        )
+    }
+
+    /// 序列化为发给游戏的 JSON 文本
+    pub(super) fn to_json(&self) -> AppResult<String> {
+        serde_json::to_string(self)
+            .map_err(|e| AppError::Server(format!("调试消息序列化失败: {e}")))
+    }
+}
+
+/// 二进制帧头部
+/// - kind: 数据类型，如 screenshot
+/// - mime: 数据的 MIME 类型，如 image/png
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BinaryHeader {
+    kind: String,
+    #[serde(default)]
+    mime: Option<String>,
+}
+
+/// 游戏发回的二进制数据，转发给编辑器时以 Base64 编码
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BinaryFrame {
+    kind: String,
+    mime: Option<String>,
+    data: String,
+}
+
+impl BinaryFrame {
+    /// 解析二进制帧
+    /// 帧格式：4 字节大端序头部长度 + JSON 头部 + 数据
+    pub(super) fn parse(frame: &[u8]) -> AppResult<Self> {
+        let invalid = |reason: &str| AppError::Server(format!("无效的二进制帧: {reason}"));
+
+        let (len, rest) = frame
+            .split_first_chunk::<BINARY_HEADER_LEN_BYTES>()
+            .ok_or_else(|| invalid("缺少头部长度"))?;
+        let len = u32::from_be_bytes(*len) as usize;
+        if rest.len() < len {
+            return Err(invalid("头部长度超出帧长度"));
+        }
+
+        let (header, payload) = rest.split_at(len);
+        let header: BinaryHeader =
+            serde_json::from_slice(header).map_err(|e| invalid(&e.to_string()))?;
+
+        Ok(Self {
+            kind: header.kind,
+            mime: header.mime,
+            data: BASE64.encode(payload),
+        })
+    }
+}
+
+/// 客户端发来的消息
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase", tag = "type", content = "data")]
+pub enum ClientPayload {
+    /// 调试协议消息
+    Debug(DebugMessage),
+    /// 二进制数据
+    Binary(BinaryFrame),
+    /// 桥接脚本的上报消息
+    Bridge(BridgeReport),
+    /// 无法解析的消息
+    /// - raw: 文本消息的原文，二进制帧为 None
+    /// - error: 解析失败的原因
+    Invalid { raw: Option<String>, error: String },
+}
+
+impl ClientPayload {
+    /// 解析客户端发来的文本消息
+    /// 事件名为桥接事件时解析为上报消息，否则按 WebGAL 调试消息校验
+    pub(super) fn from_text(text: &str) -> AppResult<Self> {
+        let mut value: Value = serde_json::from_str(text)
+            .map_err(|e| AppError::Server(format!("无效的调试消息: {e}")))?;
+
+        if value.get("event").and_then(Value::as_str) == Some(BRIDGE_EVENT) {
+            return serde_json::from_value(value["data"].take())
+                .map(Self::Bridge)
+                .map_err(|e| AppError::Server(format!("无效的桥接消息: {e}")));
+        }
+
+        serde_json::from_value(value)
+            .map(Self::Debug)
+            .map_err(|e| AppError::Server(format!("无效的调试消息: {e}")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use super::{BinaryFrame, ClientPayload, DebugMessage};
+
+    fn parse(value: Value) -> Result<DebugMessage, serde_json::Error> {
+        serde_json::from_value(value)
+    }
+
+    fn round_trip(value: Value) -> Value {
+        let message = parse(value).unwrap();
+        serde_json::from_str(&message.to_json().unwrap()).unwrap()
+    }
+
+    fn frame(header: &[u8], payload: &[u8]) -> Vec<u8> {
+        let mut frame = (header.len() as u32).to_be_bytes().to_vec();
+        frame.extend_from_slice(header);
+        frame.extend_from_slice(payload);
+        frame
+    }
+
+    #[test]
+    fn round_trips_debug_commands() {
+        let scene_msg = json!({ "scene": "start.txt", "sentence": 3 });
+        let messages = [
+            json!({ "command": 0, "sceneMsg": scene_msg, "message": "exp" }),
+            json!({ "command": 0, "sceneMsg": scene_msg, "message": "sync" }),
+            json!({ "command": 1, "sceneMsg": scene_msg, "stageSyncMsg": { "bgName": "bg.png" } }),
+            json!({ "command": 1, "sceneMsg": scene_msg }),
+            json!({ "command": 2, "sceneMsg": scene_msg }),
+            json!({ "command": 3, "message": "changeBg:bg.png -next;" }),
+            json!({ "command": 4 }),
+            json!({ "command": 5, "message": r#"[{"component":"showTitle","visibility":false}]"# }),
+            json!({ "command": 6, "message": "say:hello;" }),
+            json!({ "command": 7, "message": "true" }),
+            json!({ "command": 8, "message": r#"{"target":"stage-main"}"# }),
+        ];
+
+        for data in messages {
+            let value = json!({ "event": "message", "data": data });
+            assert_eq!(round_trip(value.clone()), value);
+        }
+    }
+
+    #[test]
+    fn jump_without_mode_is_sync() {
+        let value = json!({
+            "event": "message",
+            "data": { "command": 0, "sceneMsg": { "scene": "start.txt", "sentence": 0 } },
+        });
+        assert_eq!(round_trip(value)["data"]["message"], "sync");
+    }
+
+    #[test]
+    fn rejects_invalid_debug_commands() {
+        let invalid = [
+            json!({ "command": 0, "message": "exp" }),
+            json!({ "command": 0, "sceneMsg": { "scene": "a.txt", "sentence": 0 }, "message": "fast" }),
+            json!({ "command": 3 }),
+            json!({ "command": 5, "message": "not json" }),
+            json!({ "command": 7, "message": "yes" }),
+            json!({ "command": 8, "message": "[1, 2]" }),
+            json!({ "command": 99 }),
+        ];
+
+        for data in invalid {
+            let value = json!({ "event": "message", "data": data });
+            assert!(parse(value.clone()).is_err(), "应拒绝 {value}");
+        }
+    }
+
+    #[test]
+    fn parses_binary_frames() {
+        let frame = BinaryFrame::parse(&frame(
+            br#"{"kind":"screenshot","mime":"image/png"}"#,
+            &[1, 2, 3],
+        ))
+        .unwrap();
+        assert_eq!(frame.kind, "screenshot");
+        assert_eq!(frame.mime.as_deref(), Some("image/png"));
+        assert_eq!(frame.data, "AQID");
+
+        let frame = BinaryFrame::parse(&self::frame(br#"{"kind":"raw"}"#, &[])).unwrap();
+        assert_eq!(frame.mime, None);
+        assert_eq!(frame.data, "");
+    }
+
+    #[test]
+    fn rejects_invalid_binary_frames() {
+        assert!(BinaryFrame::parse(&[]).is_err());
+        assert!(BinaryFrame::parse(&[0, 0, 1]).is_err());
+        assert!(BinaryFrame::parse(&[0, 0, 0, 10, b'{', b'}']).is_err());
+        assert!(BinaryFrame::parse(&frame(b"not json", &[1])).is_err());
+        assert!(BinaryFrame::parse(&frame(br#"{"mime":"image/png"}"#, &[1])).is_err());
+    }
+
+    #[test]
+    fn dispatches_client_text() {
+        let debug = ClientPayload::from_text(r#"{"event":"message","data":{"command":4}}"#);
+        assert!(matches!(debug, Ok(ClientPayload::Debug(_))));
+
+        let bridge = ClientPayload::from_text(
+            r#"{"event":"webgalCraftBridge","data":{"type":"scene","data":{"scene":"start.txt","sentence":1}}}"#,
+        );
+        assert!(matches!(bridge, Ok(ClientPayload::Bridge(_))));
+
+        assert!(ClientPayload::from_text("not json").is_err());
+        assert!(ClientPayload::from_text(r#"{"event":"webgalCraftBridge","data":{}}"#).is_err());
+    }
+}
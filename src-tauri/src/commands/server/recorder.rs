@@ -0,0 +1,593 @@
+// 会话录制模块：录制并重放 /api/webgalsync 上的调试消息
+// 主要功能：
+// 1. 会话录制：将编辑器发给游戏、服务器发给游戏和游戏发回编辑器的消息按时间顺序写入 JSON Lines 文件，
+//    写入在独立的线程中进行，不阻塞消息收发
+// 2. 会话重放：按录制时的时间间隔，将编辑器发出的消息重新发送给新的预览
+// 3. 重放控制：可调整重放速度、指定目标站点，并随时停止重放
+
+use std::{
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+    sync::mpsc,
+    time::{Duration, Instant},
+};
+
+use axum::extract::ws::Message;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use serde::{Deserialize, Serialize};
+use tauri::{ipc::Channel, State as TauriState};
+use tokio::{sync::Mutex, task::JoinHandle};
+
+use super::{now_millis, AppState, ServerState};
+use crate::commands::{AppError, AppResult};
+
+/// 录制文件格式版本
+const RECORDING_VERSION: u32 = 1;
+
+/// 录制文件的首行，记录格式版本与开始时间
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RecordingHeader {
+    version: u32,
+    started_at: u64,
+}
+
+/// 消息方向
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(super) enum Direction {
+    /// 编辑器发给游戏
+    ToGame,
+    /// 游戏发回编辑器
+    FromGame,
+    /// 服务器发给游戏（如握手、实时刷新与重新同步），不参与重放
+    FromServer,
+}
+
+/// 消息的收发对象
+/// 编辑器发出的消息为发送目标，游戏发回的消息为发送方客户端
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", tag = "type", content = "id")]
+pub(super) enum Target {
+    /// 全部客户端
+    Broadcast,
+    /// 指定站点的客户端，值为站点哈希
+    Site(String),
+    /// 指定客户端，值为客户端ID
+    Client(String),
+}
+
+/// 录制的消息内容，二进制数据以 Base64 编码
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", tag = "kind", content = "data")]
+enum RecordedData {
+    Text(String),
+    Binary(String),
+}
+
+impl RecordedData {
+    fn from_message(message: &Message) -> Option<Self> {
+        match message {
+            Message::Text(text) => Some(Self::Text(text.to_string())),
+            Message::Binary(bytes) => Some(Self::Binary(BASE64.encode(bytes))),
+            _ => None,
+        }
+    }
+
+    fn into_message(self) -> AppResult<Message> {
+        Ok(match self {
+            Self::Text(text) => Message::Text(text.into()),
+            Self::Binary(data) => Message::Binary(
+                BASE64
+                    .decode(data)
+                    .map_err(|e| AppError::Server(format!("无效的二进制消息: {e}")))?
+                    .into(),
+            ),
+        })
+    }
+}
+
+/// 录制的消息
+/// 包含：
+/// - time: 相对录制开始的时间（毫秒）
+/// - direction: 消息方向
+/// - target: 收发对象
+/// - data: 消息内容
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RecordedMessage {
+    time: u64,
+    direction: Direction,
+    target: Target,
+    data: RecordedData,
+}
+
+/// 进行中的录制
+/// 包含：
+/// - tx: 待写入消息的发送器，关闭后写入线程写完剩余消息并退出
+/// - writer: 写入线程句柄，返回写入的消息数
+/// - path: 录制文件路径
+/// - started: 录制开始时间
+struct Recording {
+    tx: mpsc::Sender<RecordedMessage>,
+    writer: JoinHandle<io::Result<usize>>,
+    path: PathBuf,
+    started: Instant,
+}
+
+/// 录制结果
+/// 包含：
+/// - path: 录制文件路径
+/// - messages: 录制的消息数
+/// - duration_ms: 录制时长（毫秒）
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordingSummary {
+    path: PathBuf,
+    messages: usize,
+    duration_ms: u64,
+}
+
+/// 重放进度
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase", tag = "event", content = "data")]
+pub enum ReplayEvent {
+    /// 已发送一条消息
+    Progress { sent: usize, total: usize },
+    /// 重放完成
+    Finished { sent: usize },
+}
+
+/// 会话录制与重放状态
+/// 包含：
+/// - recording: 进行中的录制
+/// - replay: 进行中的重放任务
+#[derive(Default)]
+pub(super) struct Sessions {
+    recording: Option<Recording>,
+    replay: Option<JoinHandle<()>>,
+}
+
+impl Sessions {
+    /// 录制一条消息，未在录制时不做任何操作
+    /// 消息交给写入线程写入文件；写入线程因写入失败退出后停止录制
+    pub(super) fn record(&mut self, direction: Direction, target: Target, message: &Message) {
+        let Some(recording) = &mut self.recording else {
+            return;
+        };
+        let Some(data) = RecordedData::from_message(message) else {
+            return;
+        };
+
+        let entry = RecordedMessage {
+            time: recording.started.elapsed().as_millis() as u64,
+            direction,
+            target,
+            data,
+        };
+
+        if recording.tx.send(entry).is_err() {
+            self.recording = None;
+        }
+    }
+}
+
+/// 创建录制文件并写入文件头
+fn create_recording(path: &Path, started_at: u64) -> io::Result<BufWriter<File>> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    let mut writer = BufWriter::new(File::create(path)?);
+    let header = RecordingHeader {
+        version: RECORDING_VERSION,
+        started_at,
+    };
+    serde_json::to_writer(&mut writer, &header)?;
+    writer.write_all(b"\n")?;
+    writer.flush()?;
+    Ok(writer)
+}
+
+/// 写入线程：依次写入收到的消息，已到达的消息合并写入后再落盘
+///
+/// 返回：
+/// - 成功：发送端全部关闭（录制停止）后返回写入的消息数
+/// - 失败：写入错误
+fn write_recording(
+    mut writer: BufWriter<File>,
+    rx: mpsc::Receiver<RecordedMessage>,
+) -> io::Result<usize> {
+    let mut messages = 0;
+    while let Ok(entry) = rx.recv() {
+        for entry in std::iter::once(entry).chain(rx.try_iter()) {
+            serde_json::to_writer(&mut writer, &entry)?;
+            writer.write_all(b"\n")?;
+            messages += 1;
+        }
+        writer.flush()?;
+    }
+    Ok(messages)
+}
+
+/// 读取录制文件中编辑器发出的消息
+async fn read_recording(file: &str) -> AppResult<Vec<RecordedMessage>> {
+    parse_recording(&tokio::fs::read_to_string(file).await?)
+}
+
+/// 解析录制文件内容，只保留编辑器发出的消息
+fn parse_recording(content: &str) -> AppResult<Vec<RecordedMessage>> {
+    let mut lines = content.lines().filter(|line| !line.trim().is_empty());
+
+    let header: RecordingHeader = lines
+        .next()
+        .ok_or_else(|| AppError::Server("录制文件为空".into()))
+        .and_then(|line| {
+            serde_json::from_str(line)
+                .map_err(|e| AppError::Server(format!("无效的录制文件头: {e}")))
+        })?;
+    if header.version != RECORDING_VERSION {
+        return Err(AppError::Server(format!(
+            "不支持的录制文件版本: {}",
+            header.version
+        )));
+    }
+
+    let mut messages = Vec::new();
+    for (index, line) in lines.enumerate() {
+        let message: RecordedMessage = serde_json::from_str(line)
+            .map_err(|e| AppError::Server(format!("录制文件第 {} 条消息无效: {e}", index + 1)))?;
+        if message.direction == Direction::ToGame {
+            messages.push(message);
+        }
+    }
+
+    Ok(messages)
+}
+
+/// 发送一条重放消息
+/// 指定了目标站点时发往该站点；否则按录制时的目标发送，
+/// 录制时的客户端在新的预览中已不存在，单播消息改为广播
+async fn send_replayed(
+    state: &AppState,
+    site: Option<&str>,
+    target: Target,
+    message: Message,
+) -> AppResult<()> {
+    let room = match (site, target) {
+        (Some(site), _) => Some(site.to_string()),
+        (None, Target::Site(hash)) => Some(hash),
+        (None, Target::Broadcast | Target::Client(_)) => None,
+    };
+
+    match room {
+        Some(hash) => state.broadcast_to_room(&hash, message).await,
+        None => state
+            .broadcast_tx
+            .send(message)
+            .map(|_| ())
+            .map_err(|_| AppError::Server("Broadcast failed".into())),
+    }
+}
+
+/// 开始录制会话
+/// 之后编辑器发出的调试消息和游戏发回的消息都会写入录制文件，直到停止录制
+///
+/// 参数：
+/// - state: 服务器状态
+/// - dir: 录制文件所在目录，不存在时自动创建
+///
+/// 返回：
+/// - 成功：录制文件路径，文件名包含开始时间
+/// - 失败：错误信息（如已在录制中）
+#[tauri::command]
+pub async fn start_session_recording(
+    state: TauriState<'_, Mutex<ServerState>>,
+    dir: String,
+) -> AppResult<PathBuf> {
+    let state_guard = state.lock().await;
+    let mut sessions = state_guard.app_state.sessions.lock().await;
+    if sessions.recording.is_some() {
+        return Err(AppError::Server("已在录制会话".into()));
+    }
+
+    let started_at = now_millis();
+    let path = PathBuf::from(dir).join(format!("webgalsync-{started_at}.jsonl"));
+    let file_writer = tokio::task::spawn_blocking({
+        let path = path.clone();
+        move || create_recording(&path, started_at)
+    })
+    .await
+    .map_err(|e| AppError::Server(format!("创建录制文件失败: {e}")))??;
+
+    let (tx, rx) = mpsc::channel();
+    let writer = tokio::task::spawn_blocking(move || {
+        let result = write_recording(file_writer, rx);
+        if let Err(e) = &result {
+            log::warn!("会话录制写入失败，已停止录制: {e}");
+        }
+        result
+    });
+
+    sessions.recording = Some(Recording {
+        tx,
+        writer,
+        path: path.clone(),
+        started: Instant::now(),
+    });
+
+    Ok(path)
+}
+
+/// 停止录制会话
+/// 等待写入线程写完已录制的消息
+///
+/// 参数：
+/// - state: 服务器状态
+///
+/// 返回：
+/// - 成功：录制结果，未在录制时为 None
+/// - 失败：错误信息
+#[tauri::command]
+pub async fn stop_session_recording(
+    state: TauriState<'_, Mutex<ServerState>>,
+) -> AppResult<Option<RecordingSummary>> {
+    let app_state = state.lock().await.app_state.clone();
+    let Some(Recording {
+        tx,
+        writer,
+        path,
+        started,
+    }) = app_state.sessions.lock().await.recording.take()
+    else {
+        return Ok(None);
+    };
+
+    let duration_ms = started.elapsed().as_millis() as u64;
+    drop(tx);
+    let messages = writer
+        .await
+        .map_err(|e| AppError::Server(format!("会话录制写入线程异常: {e}")))??;
+
+    Ok(Some(RecordingSummary {
+        path,
+        messages,
+        duration_ms,
+    }))
+}
+
+/// 重放录制的会话
+/// 按录制时的时间间隔重新发送编辑器发出的消息，服务器发出和游戏发回的消息仅作记录，不参与重放。
+/// 已有重放在进行时先停止之前的重放
+///
+/// 参数：
+/// - state: 服务器状态
+/// - file: 录制文件路径
+/// - path: 目标站点目录路径，指定时所有消息都发往该站点
+/// - speed: 重放速度倍率，默认 1
+/// - on_event: 重放进度通道
+///
+/// 返回：
+/// - 成功：待重放的消息数
+/// - 失败：错误信息
+#[tauri::command]
+pub async fn replay_session(
+    state: TauriState<'_, Mutex<ServerState>>,
+    file: String,
+    path: Option<String>,
+    speed: Option<f64>,
+    on_event: Channel<ReplayEvent>,
+) -> AppResult<usize> {
+    let speed = speed.unwrap_or(1.0);
+    if !(speed.is_finite() && speed > 0.0) {
+        return Err(AppError::Server("重放速度必须大于 0".into()));
+    }
+
+    let state_guard = state.lock().await;
+    let site = match path {
+        Some(path) => Some(state_guard.resolve_site(&path)?.0),
+        None => None,
+    };
+    let messages = read_recording(&file).await?;
+    let total = messages.len();
+
+    let app_state = state_guard.app_state.clone();
+    let replay = tokio::spawn({
+        let app_state = app_state.clone();
+        async move {
+            let started = Instant::now();
+            let mut sent = 0;
+
+            for message in messages {
+                let due = Duration::from_secs_f64(message.time as f64 / 1000.0 / speed);
+                tokio::time::sleep(due.saturating_sub(started.elapsed())).await;
+
+                let result = match message.data.into_message() {
+                    Ok(data) => {
+                        send_replayed(&app_state, site.as_deref(), message.target, data).await
+                    }
+                    Err(e) => Err(e),
+                };
+                if let Err(e) = result {
+                    log::warn!("重放消息发送失败: {e}");
+                }
+
+                sent += 1;
+                let _ = on_event.send(ReplayEvent::Progress {
+                    sent,
+                    total,
+                });
+            }
+
+            let _ = on_event.send(ReplayEvent::Finished {
+                sent,
+            });
+        }
+    });
+
+    if let Some(previous) = app_state.sessions.lock().await.replay.replace(replay) {
+        previous.abort();
+    }
+
+    Ok(total)
+}
+
+/// 停止正在进行的重放
+///
+/// 参数：
+/// - state: 服务器状态
+///
+/// 返回：
+/// - 成功：空值
+/// - 失败：错误信息
+#[tauri::command]
+pub async fn stop_session_replay(state: TauriState<'_, Mutex<ServerState>>) -> AppResult<()> {
+    let state_guard = state.lock().await;
+    if let Some(replay) = state_guard.app_state.sessions.lock().await.replay.take() {
+        replay.abort();
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, sync::mpsc};
+
+    use axum::extract::ws::Message;
+
+    use super::{
+        create_recording, parse_recording, write_recording, Direction, RecordedData,
+        RecordedMessage, Target,
+    };
+
+    const HEADER: &str = r#"{"version":1,"startedAt":1700000000000}"#;
+
+    fn line(time: u64, direction: &str, target: &str, data: &str) -> String {
+        format!(r#"{{"time":{time},"direction":"{direction}","target":{target},"data":{data}}}"#)
+    }
+
+    fn text(message: Message) -> String {
+        match message {
+            Message::Text(text) => text.to_string(),
+            other => panic!("不是文本消息: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn keeps_only_messages_to_game() {
+        let content = [
+            HEADER.to_string(),
+            line(
+                0,
+                "fromServer",
+                r#"{"type":"client","id":"a"}"#,
+                r#"{"kind":"text","data":"hello"}"#,
+            ),
+            line(
+                10,
+                "toGame",
+                r#"{"type":"broadcast"}"#,
+                r#"{"kind":"text","data":"first"}"#,
+            ),
+            line(
+                20,
+                "fromGame",
+                r#"{"type":"client","id":"a"}"#,
+                r#"{"kind":"text","data":"reply"}"#,
+            ),
+            String::new(),
+            line(
+                30,
+                "toGame",
+                r#"{"type":"site","id":"site"}"#,
+                r#"{"kind":"binary","data":"AQID"}"#,
+            ),
+        ]
+        .join("\n");
+
+        let messages = parse_recording(&content).unwrap();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].time, 10);
+        assert!(matches!(messages[0].target, Target::Broadcast));
+        assert_eq!(
+            text(messages[0].data.clone().into_message().unwrap()),
+            "first"
+        );
+        assert_eq!(messages[1].time, 30);
+        assert!(matches!(&messages[1].target, Target::Site(hash) if hash == "site"));
+        assert_eq!(
+            messages[1].data.clone().into_message().unwrap(),
+            Message::Binary(vec![1, 2, 3].into())
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_recordings() {
+        assert!(parse_recording("").is_err());
+        assert!(parse_recording("\n\n").is_err());
+        assert!(parse_recording("not json").is_err());
+        assert!(parse_recording(r#"{"version":2,"startedAt":0}"#).is_err());
+
+        let content = format!(
+            "{HEADER}\n{}\n{{",
+            line(
+                0,
+                "toGame",
+                r#"{"type":"broadcast"}"#,
+                r#"{"kind":"text","data":"ok"}"#
+            )
+        );
+        let error = parse_recording(&content).unwrap_err().to_string();
+        assert!(error.contains("第 2 条消息"), "{error}");
+    }
+
+    #[test]
+    fn reads_back_written_recording() {
+        let path = std::env::temp_dir()
+            .join(format!("webgal-craft-recorder-{}", std::process::id()))
+            .join("session.jsonl");
+        let writer = create_recording(&path, 1_700_000_000_000).unwrap();
+
+        let (tx, rx) = mpsc::channel();
+        let entries = [
+            (Direction::ToGame, Target::Broadcast, "scene"),
+            (
+                Direction::FromGame,
+                Target::Client("a".to_string()),
+                "reply",
+            ),
+            (
+                Direction::FromServer,
+                Target::Site("site".to_string()),
+                "reload",
+            ),
+            (
+                Direction::ToGame,
+                Target::Client("a".to_string()),
+                "unicast",
+            ),
+        ];
+        for (time, (direction, target, data)) in (0..).zip(entries) {
+            tx.send(RecordedMessage {
+                time,
+                direction,
+                target,
+                data: RecordedData::Text(data.to_string()),
+            })
+            .unwrap();
+        }
+        drop(tx);
+        assert_eq!(write_recording(writer, rx).unwrap(), 4);
+
+        let content = fs::read_to_string(&path).unwrap();
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+
+        let messages: Vec<_> = parse_recording(&content)
+            .unwrap()
+            .into_iter()
+            .map(|message| text(message.data.into_message().unwrap()))
+            .collect();
+        assert_eq!(messages, ["scene", "unicast"]);
+    }
+}
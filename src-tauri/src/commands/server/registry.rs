@@ -0,0 +1,251 @@
+// 站点注册表模块：为静态站点分配稳定的站点ID并持久化
+// 主要功能：
+// 1. 稳定哈希：基于标准化路径的 blake3 哈希生成站点ID，不随 Rust 版本变化
+// 2. 站点别名：允许用户为站点指定易读的别名，设置后作为站点ID使用
+// 3. 持久化：注册表保存到应用数据目录，先写入临时文件再重命名，应用重启或更新后预览地址保持不变
+// 4. 容量限制：加载时清理已不存在的目录，条目过多时移除最早注册且未设置别名的条目
+
+use std::{
+    fs::{self, File},
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::commands::{AppError, AppResult};
+
+/// 站点哈希的十六进制长度
+const SITE_HASH_LEN: usize = 16;
+
+/// 站点别名的最大长度
+const ALIAS_MAX_LEN: usize = 64;
+
+/// 注册表的最大条目数
+const MAX_ENTRIES: usize = 256;
+
+/// 注册表条目
+/// 包含：
+/// - hash: 由标准化路径计算的稳定哈希
+/// - alias: 用户指定的别名
+/// - path: 标准化后的站点目录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SiteEntry {
+    hash: String,
+    alias: Option<String>,
+    path: PathBuf,
+}
+
+impl SiteEntry {
+    /// 站点ID：设置了别名时为别名，否则为哈希
+    pub(super) fn id(&self) -> &str {
+        self.alias.as_deref().unwrap_or(&self.hash)
+    }
+}
+
+/// 站点注册表
+/// - file: 持久化文件路径，为 None 时仅保存在内存中
+/// - entries: 注册表条目
+#[derive(Default)]
+pub(super) struct SiteRegistry {
+    file: Option<PathBuf>,
+    entries: Vec<SiteEntry>,
+}
+
+impl SiteRegistry {
+    /// 从持久化文件加载注册表，文件不存在或无法解析时从空注册表开始
+    /// 目录已不存在且未设置别名的条目不再保留
+    pub(super) fn load(file: PathBuf) -> Self {
+        let mut entries: Vec<SiteEntry> = match fs::read_to_string(&file) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                log::warn!("站点注册表解析失败，将重新创建: {e}");
+                Vec::new()
+            }),
+            Err(_) => Vec::new(),
+        };
+        entries.retain(|entry| entry.alias.is_some() || entry.path.is_dir());
+
+        Self {
+            file: Some(file),
+            entries,
+        }
+    }
+
+    /// 获取注册表条目列表
+    pub(super) fn entries(&self) -> &[SiteEntry] {
+        &self.entries
+    }
+
+    /// 获取站点目录对应的站点ID，只查询不注册
+    /// 未注册的目录返回其稳定哈希，与注册后未设置别名时的站点ID一致
+    ///
+    /// 参数：
+    /// - path: 标准化后的站点目录
+    pub(super) fn site_id(&self, path: &Path) -> String {
+        match self.entries.iter().find(|entry| entry.path == path) {
+            Some(entry) => entry.id().to_string(),
+            None => stable_hash(path),
+        }
+    }
+
+    /// 注册站点目录，已注册时不做任何操作
+    /// 条目数超过上限时移除最早注册且未设置别名的条目
+    ///
+    /// 参数：
+    /// - path: 标准化后的站点目录
+    ///
+    /// 返回：是否新增了条目，新增后需调用 save 持久化
+    pub(super) fn register(&mut self, path: &Path) -> bool {
+        if self.entries.iter().any(|entry| entry.path == path) {
+            return false;
+        }
+
+        if self.entries.len() >= MAX_ENTRIES {
+            if let Some(oldest) = self.entries.iter().position(|entry| entry.alias.is_none()) {
+                self.entries.remove(oldest);
+            }
+        }
+
+        self.entries.push(SiteEntry {
+            hash: stable_hash(path),
+            alias: None,
+            path: path.to_path_buf(),
+        });
+        true
+    }
+
+    /// 设置或清除站点别名，站点目录未注册时先注册
+    /// 修改只保存在内存中，需调用 save 持久化
+    ///
+    /// 参数：
+    /// - path: 标准化后的站点目录
+    /// - alias: 新别名，为 None 时清除别名
+    pub(super) fn set_alias(
+        &mut self,
+        path: &Path,
+        alias: Option<String>,
+    ) -> AppResult<&SiteEntry> {
+        if let Some(alias) = &alias {
+            validate_alias(alias)?;
+
+            let taken = self.entries.iter().any(|entry| {
+                entry.path != path && (entry.hash == *alias || entry.alias.as_ref() == Some(alias))
+            });
+            if taken {
+                return Err(AppError::Server(format!("站点别名已被占用: {alias}")));
+            }
+        }
+
+        self.register(path);
+        let entry = self
+            .entries
+            .iter_mut()
+            .find(|entry| entry.path == path)
+            .ok_or_else(|| AppError::Server("站点注册失败".into()))?;
+        entry.alias = alias;
+
+        Ok(entry)
+    }
+
+    /// 将注册表写入持久化文件
+    /// 先写入同目录的临时文件再重命名，写入中途崩溃不会截断已有的注册表
+    pub(super) async fn save(&self) -> AppResult<()> {
+        let Some(file) = self.file.clone() else {
+            return Ok(());
+        };
+
+        let content = serde_json::to_string_pretty(&self.entries)
+            .map_err(|e| AppError::Server(format!("站点注册表序列化失败: {e}")))?;
+
+        tokio::task::spawn_blocking(move || write_file(&file, content.as_bytes()))
+            .await
+            .map_err(|e| AppError::Server(format!("站点注册表写入失败: {e}")))?
+    }
+}
+
+/// 原子地写入文件：先写入同目录的临时文件，落盘后再重命名覆盖原文件
+fn write_file(path: &Path, content: &[u8]) -> AppResult<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let temp = temp_path(path)?;
+    if let Err(e) = write_synced(&temp, content).and_then(|()| fs::rename(&temp, path)) {
+        let _ = fs::remove_file(&temp);
+        return Err(e.into());
+    }
+
+    Ok(())
+}
+
+/// 写入文件并等待数据落盘
+fn write_synced(path: &Path, content: &[u8]) -> std::io::Result<()> {
+    let mut file = File::create(path)?;
+    file.write_all(content)?;
+    file.sync_all()
+}
+
+/// 同目录下的临时文件路径，重命名时不会跨文件系统
+fn temp_path(path: &Path) -> AppResult<PathBuf> {
+    let mut suffix = [0u8; 6];
+    getrandom::fill(&mut suffix)
+        .map_err(|e| AppError::Server(format!("生成临时文件名失败: {e}")))?;
+    let suffix: String = suffix.iter().map(|byte| format!("{byte:02x}")).collect();
+
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".{suffix}.tmp"));
+    Ok(path.with_file_name(name))
+}
+
+/// 计算站点目录的稳定哈希
+/// 使用 blake3 对标准化路径的字节进行哈希，结果不随 Rust 版本或进程变化
+fn stable_hash(path: &Path) -> String {
+    let hash = blake3::hash(&path_bytes(path));
+    hash.to_hex()[..SITE_HASH_LEN].to_string()
+}
+
+/// 路径的字节表示
+/// 优先使用 UTF-8 编码；无法转换为 UTF-8 的路径在 Windows 上使用 UTF-16 小端字节，其他平台使用原始字节
+fn path_bytes(path: &Path) -> Vec<u8> {
+    if let Some(text) = path.to_str() {
+        return text.as_bytes().to_vec();
+    }
+
+    #[cfg(windows)]
+    {
+        use std::os::windows::ffi::OsStrExt;
+        path.as_os_str()
+            .encode_wide()
+            .flat_map(u16::to_le_bytes)
+            .collect()
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStrExt;
+        path.as_os_str().as_bytes().to_vec()
+    }
+    #[cfg(not(any(windows, unix)))]
+    {
+        path.to_string_lossy().as_bytes().to_vec()
+    }
+}
+
+/// 校验站点别名
+/// 别名会出现在预览地址中，只允许小写字母、数字和连字符，且不能以连字符开头
+fn validate_alias(alias: &str) -> AppResult<()> {
+    let valid = !alias.is_empty()
+        && alias.len() <= ALIAS_MAX_LEN
+        && !alias.starts_with('-')
+        && alias
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-');
+
+    if valid {
+        Ok(())
+    } else {
+        Err(AppError::Server(format!(
+            "无效的站点别名: {alias}（仅允许小写字母、数字和连字符，且长度不超过 {ALIAS_MAX_LEN}）"
+        )))
+    }
+}
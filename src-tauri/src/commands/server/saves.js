@@ -0,0 +1,117 @@
+// WebGAL Craft 存档同步脚本
+// 由预览服务器注入到站点首页，将 WebGAL 写入浏览器存储（localforage / IndexedDB）的存档
+// 同步到项目的 .webgal-craft/saves/ 目录，并在游戏启动前把项目中的存档写回浏览器存储。
+(function () {
+  'use strict'
+
+  if (window.__WEBGAL_CRAFT_SAVES__) {
+    return
+  }
+  window.__WEBGAL_CRAFT_SAVES__ = true
+
+  var SITE = window.__WEBGAL_CRAFT_SITE__
+  var DB_NAME = 'localforage'
+  var STORE_NAME = 'keyvaluepairs'
+  var SAVE_KEY = /-saves(\d+|-fast)$/
+  var ENDPOINT = '/api/saves/' + encodeURIComponent(SITE)
+
+  if (!SITE || typeof indexedDB === 'undefined') {
+    return
+  }
+
+  function isSaveKey(key) {
+    return typeof key === 'string' && SAVE_KEY.test(key)
+  }
+
+  function saveUrl(key) {
+    return ENDPOINT + '/' + encodeURIComponent(key)
+  }
+
+  function upload(key, value) {
+    var body
+    try {
+      body = JSON.stringify(value)
+    } catch (e) {
+      return
+    }
+    fetch(saveUrl(key), {
+      method: 'PUT',
+      headers: { 'Content-Type': 'application/json' },
+      body: body,
+      keepalive: body.length < 60000,
+    }).catch(function () {})
+  }
+
+  function remove(key) {
+    fetch(saveUrl(key), { method: 'DELETE', keepalive: true }).catch(function () {})
+  }
+
+  function isLocalforageStore(store) {
+    return store.name === STORE_NAME && store.transaction.db.name === DB_NAME
+  }
+
+  // 同步写入：写入成功后再上传，避免上传未能保存的存档
+  var nativePut = IDBObjectStore.prototype.put
+  IDBObjectStore.prototype.put = function (value, key) {
+    var request = nativePut.apply(this, arguments)
+    if (isSaveKey(key) && isLocalforageStore(this)) {
+      request.addEventListener('success', function () {
+        upload(key, value)
+      })
+    }
+    return request
+  }
+
+  var nativeDelete = IDBObjectStore.prototype.delete
+  IDBObjectStore.prototype.delete = function (key) {
+    var request = nativeDelete.apply(this, arguments)
+    if (isSaveKey(key) && isLocalforageStore(this)) {
+      request.addEventListener('success', function () {
+        remove(key)
+      })
+    }
+    return request
+  }
+
+  // 恢复存档：同步读取项目中的存档，并抢在游戏之前打开数据库写入。
+  // IndexedDB 按创建顺序执行作用域重叠的事务，游戏随后的读取能看到这些存档
+  var saves
+  try {
+    var xhr = new XMLHttpRequest()
+    xhr.open('GET', ENDPOINT, false)
+    xhr.send()
+    if (xhr.status !== 200) {
+      return
+    }
+    saves = JSON.parse(xhr.responseText)
+  } catch (e) {
+    return
+  }
+
+  var keys = Object.keys(saves)
+  if (keys.length === 0) {
+    return
+  }
+
+  var open = indexedDB.open(DB_NAME)
+  open.onupgradeneeded = function () {
+    if (!open.result.objectStoreNames.contains(STORE_NAME)) {
+      open.result.createObjectStore(STORE_NAME)
+    }
+  }
+  open.onsuccess = function () {
+    var db = open.result
+    if (!db.objectStoreNames.contains(STORE_NAME)) {
+      db.close()
+      return
+    }
+    var transaction = db.transaction(STORE_NAME, 'readwrite')
+    var store = transaction.objectStore(STORE_NAME)
+    keys.forEach(function (key) {
+      nativePut.call(store, saves[key], key)
+    })
+    transaction.oncomplete = transaction.onabort = function () {
+      db.close()
+    }
+  }
+})()
@@ -0,0 +1,525 @@
+// 预览存档模块：将预览中产生的 WebGAL 存档保存到项目目录
+// 主要功能：
+// 1. 存档同步：注入的脚本将浏览器存储中的存档写入 .webgal-craft/saves/，并在启动时恢复
+// 2. 存档接口：供注入脚本读取、写入和删除存档的 HTTP 接口，仅对开启了存档同步的站点可用
+// 3. 存档管理：列出、查看、删除和导入存档，便于在测试人员之间传递存档
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::UNIX_EPOCH,
+};
+
+use axum::{
+    extract::{Path as AxumPath, State as AxumState},
+    http::StatusCode,
+    Json,
+};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use tauri::State as TauriState;
+use tokio::sync::Mutex;
+
+use super::{AppState, ServerState};
+use crate::commands::{AppError, AppResult};
+
+/// 存档同步脚本，由 bridge 子模块注入到站点首页
+pub(super) const SAVES_SCRIPT: &str = include_str!("saves.js");
+
+/// 存档目录，相对于项目目录
+const SAVES_DIR: [&str; 2] = [".webgal-craft", "saves"];
+
+/// 存档文件扩展名
+const SAVE_EXTENSION: &str = "json";
+
+/// 存档键的最大长度
+const MAX_KEY_LEN: usize = 200;
+
+/// Windows 保留的设备名，不区分大小写，带扩展名时同样无法用作文件名
+const RESERVED_NAMES: [&str; 4] = ["CON", "PRN", "AUX", "NUL"];
+
+/// Windows 保留的带序号设备名前缀，后接 1 到 9，如 COM1、LPT9
+const RESERVED_NUMBERED_NAMES: [&str; 2] = ["COM", "LPT"];
+
+/// 存档接口允许的最大请求体字节数，存档中包含截图，远大于普通请求
+pub(super) const MAX_SAVE_SIZE: usize = 32 * 1024 * 1024;
+
+/// 存档信息
+/// 包含：
+/// - key: 存档键，即 WebGAL 在浏览器存储中使用的键，如 `{游戏标识}-saves3`
+/// - size: 存档文件字节数
+/// - modified: 存档文件修改时间（Unix 毫秒）
+/// - save_time: WebGAL 记录的存档时间
+/// - scene: 存档所在的场景
+/// - sentence: 存档所在的语句序号
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SaveSlot {
+    key: String,
+    size: u64,
+    modified: Option<u64>,
+    save_time: Option<String>,
+    scene: Option<String>,
+    sentence: Option<u64>,
+}
+
+/// 获取项目的存档目录
+fn saves_dir(root: &Path) -> PathBuf {
+    SAVES_DIR
+        .iter()
+        .fold(root.to_path_buf(), |dir, segment| dir.join(segment))
+}
+
+/// 是否为 Windows 保留的设备名，只比较第一个 `.` 之前的部分
+fn is_reserved_name(key: &str) -> bool {
+    let stem = key.split('.').next().unwrap_or_default().trim_end();
+    RESERVED_NAMES
+        .iter()
+        .any(|name| stem.eq_ignore_ascii_case(name))
+        || RESERVED_NUMBERED_NAMES.iter().any(|prefix| {
+            stem.len() == prefix.len() + 1
+                && stem
+                    .get(..prefix.len())
+                    .is_some_and(|head| head.eq_ignore_ascii_case(prefix))
+                && matches!(stem.as_bytes()[prefix.len()], b'1'..=b'9')
+        })
+}
+
+/// 校验存档键并返回存档文件路径
+/// 存档键直接用作文件名，因此不能包含路径分隔符或 Windows 文件名中不允许的字符，
+/// 不能以 `.` 开头，也不能是 Windows 保留的设备名
+fn save_file(root: &Path, key: &str) -> AppResult<PathBuf> {
+    let valid = !key.is_empty()
+        && key.len() <= MAX_KEY_LEN
+        && !key.starts_with('.')
+        && !key.chars().any(|c| {
+            matches!(c, '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|') || c.is_control()
+        })
+        && !is_reserved_name(key);
+    if !valid {
+        return Err(AppError::Server(format!("无效的存档键: {key}")));
+    }
+
+    Ok(saves_dir(root).join(format!("{key}.{SAVE_EXTENSION}")))
+}
+
+/// 读取存档文件
+fn read_save(file: &Path) -> AppResult<Value> {
+    let content = fs::read_to_string(file)?;
+    serde_json::from_str(&content).map_err(|e| AppError::Server(format!("无效的存档文件: {e}")))
+}
+
+/// 写入存档文件，目录不存在时自动创建
+fn write_save(file: &Path, value: &Value) -> AppResult<()> {
+    if let Some(parent) = file.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let content = serde_json::to_string(value)
+        .map_err(|e| AppError::Server(format!("存档序列化失败: {e}")))?;
+    fs::write(file, content)?;
+    Ok(())
+}
+
+/// 读取存档文件并提取存档信息
+fn slot_info(key: String, file: &Path) -> AppResult<SaveSlot> {
+    let metadata = fs::metadata(file)?;
+    let modified = metadata
+        .modified()
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map(|duration| duration.as_millis() as u64);
+
+    let save = read_save(file).unwrap_or(Value::Null);
+    let scene_data = save.get("sceneData");
+
+    Ok(SaveSlot {
+        key,
+        size: metadata.len(),
+        modified,
+        save_time: save
+            .get("saveTime")
+            .and_then(Value::as_str)
+            .map(str::to_owned),
+        scene: scene_data
+            .and_then(|data| data.get("sceneName"))
+            .and_then(Value::as_str)
+            .map(str::to_owned),
+        sentence: scene_data
+            .and_then(|data| data.get("currentSentenceId"))
+            .and_then(Value::as_u64),
+    })
+}
+
+/// 列出存档目录中的存档键
+fn save_keys(root: &Path) -> AppResult<Vec<String>> {
+    let entries = match fs::read_dir(saves_dir(root)) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+
+    let mut keys: Vec<_> = entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == SAVE_EXTENSION))
+        .filter_map(|path| Some(path.file_stem()?.to_str()?.to_owned()))
+        .collect();
+    keys.sort();
+
+    Ok(keys)
+}
+
+/// 列出存档目录中全部存档的信息
+fn list_slots(root: &Path) -> AppResult<Vec<SaveSlot>> {
+    save_keys(root)?
+        .into_iter()
+        .map(|key| {
+            let file = save_file(root, &key)?;
+            slot_info(key, &file)
+        })
+        .collect()
+}
+
+/// 删除存档文件
+///
+/// 返回：
+/// - 成功：存档文件存在并已删除时为 true，存档不存在时为 false
+/// - 失败：错误信息
+fn remove_save(file: &Path) -> AppResult<bool> {
+    match fs::remove_file(file) {
+        Ok(()) => Ok(true),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(false),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// 校验存档文件为 JSON 后复制到存档目录，并返回导入后的存档信息
+fn import_save_file(root: &Path, source: &Path, key: String) -> AppResult<SaveSlot> {
+    let save = read_save(source)?;
+    let target = save_file(root, &key)?;
+    write_save(&target, &save)?;
+    slot_info(key, &target)
+}
+
+/// 读取存档目录中的全部存档，无法读取的存档记录警告后跳过
+fn read_saves(root: &Path) -> AppResult<Map<String, Value>> {
+    let mut saves = Map::new();
+    for key in save_keys(root)? {
+        let Ok(file) = save_file(root, &key) else {
+            continue;
+        };
+        match read_save(&file) {
+            Ok(save) => {
+                saves.insert(key, save);
+            }
+            Err(e) => log::warn!("已跳过无法读取的存档 {key}: {e}"),
+        }
+    }
+    Ok(saves)
+}
+
+/// 在阻塞线程池中执行存档文件操作，避免阻塞处理请求的异步任务
+async fn run_blocking<T: Send + 'static>(
+    task: impl FnOnce() -> AppResult<T> + Send + 'static,
+) -> AppResult<T> {
+    tokio::task::spawn_blocking(task)
+        .await
+        .map_err(|e| AppError::Server(format!("存档文件操作失败: {e}")))?
+}
+
+/// 获取开启了存档同步的已挂载站点的目录
+/// 站点未挂载或未开启存档同步时返回 404，存档接口对该站点不可用
+async fn site_root(state: &AppState, hash: &str) -> Result<PathBuf, StatusCode> {
+    state
+        .sites
+        .read()
+        .await
+        .get(hash)
+        .filter(|site| site.options.persist_saves)
+        .map(|site| site.path.clone())
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+/// 存档接口：读取站点的全部存档
+/// 返回以存档键为键的 JSON 对象，供注入脚本在游戏启动前写回浏览器存储
+pub(super) async fn handle_get_saves(
+    AxumState(state): AxumState<Arc<AppState>>,
+    AxumPath(hash): AxumPath<String>,
+) -> Result<Json<Map<String, Value>>, StatusCode> {
+    let root = site_root(&state, &hash).await?;
+    match run_blocking(move || read_saves(&root)).await {
+        Ok(saves) => Ok(Json(saves)),
+        Err(e) => {
+            log::warn!("存档读取失败: {e}");
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// 存档接口：写入一个存档
+pub(super) async fn handle_put_save(
+    AxumState(state): AxumState<Arc<AppState>>,
+    AxumPath((hash, key)): AxumPath<(String, String)>,
+    Json(save): Json<Value>,
+) -> StatusCode {
+    let root = match site_root(&state, &hash).await {
+        Ok(root) => root,
+        Err(status) => return status,
+    };
+    let Ok(file) = save_file(&root, &key) else {
+        return StatusCode::BAD_REQUEST;
+    };
+
+    match run_blocking(move || write_save(&file, &save)).await {
+        Ok(()) => StatusCode::NO_CONTENT,
+        Err(e) => {
+            log::warn!("存档 {key} 写入失败: {e}");
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    }
+}
+
+/// 存档接口：删除一个存档，存档不存在时同样视为成功
+pub(super) async fn handle_delete_save(
+    AxumState(state): AxumState<Arc<AppState>>,
+    AxumPath((hash, key)): AxumPath<(String, String)>,
+) -> StatusCode {
+    let root = match site_root(&state, &hash).await {
+        Ok(root) => root,
+        Err(status) => return status,
+    };
+    let Ok(file) = save_file(&root, &key) else {
+        return StatusCode::BAD_REQUEST;
+    };
+
+    match run_blocking(move || remove_save(&file)).await {
+        Ok(_) => StatusCode::NO_CONTENT,
+        Err(e) => {
+            log::warn!("存档 {key} 删除失败: {e}");
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    }
+}
+
+/// 列出项目的预览存档
+///
+/// 参数：
+/// - state: 服务器状态
+/// - path: 静态站点目录路径
+///
+/// 返回：
+/// - 成功：存档信息列表，按存档键排序
+/// - 失败：错误信息
+#[tauri::command]
+pub async fn list_saves(
+    state: TauriState<'_, Mutex<ServerState>>,
+    path: String,
+) -> AppResult<Vec<SaveSlot>> {
+    let (_, root) = state.lock().await.resolve_site(&path)?;
+    run_blocking(move || list_slots(&root)).await
+}
+
+/// 查看存档内容
+///
+/// 参数：
+/// - state: 服务器状态
+/// - path: 静态站点目录路径
+/// - key: 存档键
+///
+/// 返回：
+/// - 成功：WebGAL 存档数据
+/// - 失败：错误信息
+#[tauri::command]
+pub async fn get_save(
+    state: TauriState<'_, Mutex<ServerState>>,
+    path: String,
+    key: String,
+) -> AppResult<Value> {
+    let (_, root) = state.lock().await.resolve_site(&path)?;
+    let file = save_file(&root, &key)?;
+    run_blocking(move || read_save(&file)).await
+}
+
+/// 删除存档
+/// 只删除项目中的存档文件，预览的浏览器存储中已恢复的存档不受影响
+///
+/// 参数：
+/// - state: 服务器状态
+/// - path: 静态站点目录路径
+/// - key: 存档键
+///
+/// 返回：
+/// - 成功：空值
+/// - 失败：错误信息
+#[tauri::command]
+pub async fn delete_save(
+    state: TauriState<'_, Mutex<ServerState>>,
+    path: String,
+    key: String,
+) -> AppResult<()> {
+    let (_, root) = state.lock().await.resolve_site(&path)?;
+    let file = save_file(&root, &key)?;
+    if !run_blocking(move || remove_save(&file)).await? {
+        return Err(AppError::Server(format!("存档不存在: {key}")));
+    }
+    Ok(())
+}
+
+/// 导入存档文件
+/// 校验文件为 JSON 后复制到项目的存档目录，预览重新加载后生效
+///
+/// 参数：
+/// - state: 服务器状态
+/// - path: 静态站点目录路径
+/// - file: 待导入的存档文件路径
+/// - key: 存档键，为 None 时使用文件名（不含扩展名）
+///
+/// 返回：
+/// - 成功：导入后的存档信息
+/// - 失败：错误信息
+#[tauri::command]
+pub async fn import_save(
+    state: TauriState<'_, Mutex<ServerState>>,
+    path: String,
+    file: String,
+    key: Option<String>,
+) -> AppResult<SaveSlot> {
+    let (_, root) = state.lock().await.resolve_site(&path)?;
+
+    let source = PathBuf::from(&file);
+    let key = match key {
+        Some(key) => key,
+        None => source
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .map(str::to_owned)
+            .ok_or_else(|| AppError::Server(format!("无法从文件名确定存档键: {file}")))?,
+    };
+
+    run_blocking(move || import_save_file(&root, &source, key)).await
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fs,
+        path::{Path, PathBuf},
+    };
+
+    use serde_json::json;
+
+    use super::{
+        import_save_file, list_slots, read_saves, remove_save, save_file, saves_dir, write_save,
+    };
+
+    /// 测试用的临时项目目录，每个测试使用独立的目录
+    fn temp_root(name: &str) -> PathBuf {
+        let root =
+            std::env::temp_dir().join(format!("webgal-craft-saves-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        root
+    }
+
+    fn is_valid(key: &str) -> bool {
+        save_file(Path::new("root"), key).is_ok()
+    }
+
+    #[test]
+    fn accepts_webgal_save_keys() {
+        assert!(is_valid("WebGAL-Demo-saves3"));
+        assert!(is_valid("游戏-saves"));
+        assert!(is_valid("console-log"));
+        assert!(is_valid("COM10"));
+    }
+
+    #[test]
+    fn rejects_invalid_characters() {
+        for key in [
+            "", ".hidden", "a/b", "a\\b", "a:b", "a*b", "a?b", "a\"b", "a<b", "a>b", "a|b", "a\nb",
+        ] {
+            assert!(!is_valid(key), "{key:?}");
+        }
+        assert!(!is_valid(&"a".repeat(201)));
+    }
+
+    #[test]
+    fn rejects_reserved_names() {
+        for key in [
+            "CON",
+            "con",
+            "Nul",
+            "prn.saves",
+            "AUX ",
+            "COM1",
+            "lpt9",
+            "LPT1.bak",
+        ] {
+            assert!(!is_valid(key), "{key:?}");
+        }
+    }
+
+    #[test]
+    fn reads_back_written_saves() {
+        let root = temp_root("read");
+        let save = json!({
+            "saveTime": "2024/1/1 12:00",
+            "sceneData": { "sceneName": "start.txt", "currentSentenceId": 3 },
+        });
+        write_save(&save_file(&root, "demo-saves").unwrap(), &save).unwrap();
+        write_save(&save_file(&root, "demo-fastSaves").unwrap(), &json!([])).unwrap();
+        // 无法解析的存档被跳过，不影响其他存档
+        fs::write(saves_dir(&root).join("broken.json"), "{").unwrap();
+
+        let saves = read_saves(&root).unwrap();
+        let slots = list_slots(&root).unwrap();
+        fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(saves.len(), 2);
+        assert_eq!(saves["demo-saves"], save);
+        assert_eq!(saves["demo-fastSaves"], json!([]));
+
+        let keys: Vec<_> = slots.iter().map(|slot| slot.key.as_str()).collect();
+        assert_eq!(keys, ["broken", "demo-fastSaves", "demo-saves"]);
+        assert_eq!(slots[2].scene.as_deref(), Some("start.txt"));
+        assert_eq!(slots[2].sentence, Some(3));
+    }
+
+    #[test]
+    fn reads_nothing_without_saves_dir() {
+        let root = temp_root("empty");
+        assert!(read_saves(&root).unwrap().is_empty());
+        assert!(list_slots(&root).unwrap().is_empty());
+    }
+
+    #[test]
+    fn removes_saves() {
+        let root = temp_root("remove");
+        let file = save_file(&root, "demo-saves").unwrap();
+        write_save(&file, &json!({})).unwrap();
+
+        assert!(remove_save(&file).unwrap());
+        assert!(!file.exists());
+        assert!(!remove_save(&file).unwrap());
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn imports_only_json_saves() {
+        let root = temp_root("import");
+        fs::create_dir_all(&root).unwrap();
+        let source = root.join("shared.json");
+        fs::write(&source, r#"{"saveTime":"now"}"#).unwrap();
+        let invalid = root.join("invalid.json");
+        fs::write(&invalid, "not json").unwrap();
+
+        let slot = import_save_file(&root, &source, "demo-saves".to_string()).unwrap();
+        let imported = import_save_file(&root, &invalid, "other-saves".to_string());
+        let saves = read_saves(&root).unwrap();
+        fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(slot.save_time.as_deref(), Some("now"));
+        assert!(imported.is_err());
+        assert_eq!(saves.keys().collect::<Vec<_>>(), ["demo-saves"]);
+    }
+}
@@ -0,0 +1,207 @@
+// 服务器守护模块：运行 HTTP 服务器并监控其生命周期
+// 主要功能：
+// 1. 端口绑定：端口被占用时自动选择新端口
+// 2. 异常通知：服务器意外退出或端口变化时通知前端
+// 3. 自动重启：可选地在服务器意外退出后重新启动
+// 4. 客户端事件：WebSocket 客户端连接与断开时通知前端
+
+use std::{
+    net::SocketAddr,
+    time::{Duration, Instant},
+};
+
+use axum::Router;
+use portpicker::pick_unused_port;
+use serde::Serialize;
+use tauri::ipc::Channel;
+use tokio::{
+    net::TcpListener,
+    sync::{oneshot, watch},
+};
+
+use super::{heartbeat::DisconnectReason, ClientInfo};
+use crate::commands::{AppError, AppResult};
+
+/// 自动重启的最大连续次数
+const MAX_RESTART_ATTEMPTS: u32 = 5;
+
+/// 自动重启的基础等待时间，每次连续重启递增
+const RESTART_DELAY: Duration = Duration::from_secs(1);
+
+/// 稳定运行超过该时长后重置连续重启计数
+const RESTART_RESET_AFTER: Duration = Duration::from_secs(60);
+
+/// 服务器生命周期事件
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase", tag = "event", content = "data")]
+pub enum ServerEvent {
+    /// 请求的端口不可用，服务器改用了其他端口
+    PortChanged {
+        requested: u16,
+        actual: u16,
+        url: String,
+    },
+    /// 服务器意外退出
+    Crashed { error: String, restarting: bool },
+    /// 服务器已自动重启
+    Restarted { url: String },
+    /// 服务器已按请求停止
+    Stopped,
+    /// WebSocket 客户端已连接
+    ClientConnected { client: ClientInfo },
+    /// WebSocket 客户端已断开
+    ClientDisconnected {
+        client: ClientInfo,
+        reason: DisconnectReason,
+    },
+    /// WebSocket 客户端处理过慢，跳过了部分广播消息，需要重新同步
+    ClientLagged { client_id: String, skipped: u64 },
+}
+
+/// 服务器监听信息
+/// - addr: 实际监听地址
+/// - started_at: 本次监听的开始时间
+#[derive(Debug, Clone, Copy)]
+pub(super) struct ListenInfo {
+    pub(super) addr: SocketAddr,
+    pub(super) started_at: Instant,
+}
+
+/// 守护选项
+/// - host: 服务器主机地址
+/// - port: 请求的端口号，重启时优先使用
+/// - auto_restart: 意外退出后是否自动重启
+pub(super) struct SupervisorOptions {
+    pub(super) host: String,
+    pub(super) port: u16,
+    pub(super) auto_restart: bool,
+}
+
+/// 绑定监听端口，端口被占用时自动选择新端口
+pub(super) async fn bind_listener(host: &str, port: u16) -> AppResult<TcpListener> {
+    match TcpListener::bind(format!("{host}:{port}")).await {
+        Ok(listener) => Ok(listener),
+        Err(_) => {
+            let new_port =
+                pick_unused_port().ok_or_else(|| AppError::Server("无法找到可用端口".into()))?;
+            Ok(TcpListener::bind(format!("{host}:{new_port}")).await?)
+        }
+    }
+}
+
+/// 运行并守护服务器
+/// 收到关闭信号时优雅关闭；意外退出时发送 Crashed 事件，
+/// 开启自动重启时重新绑定端口并继续运行
+///
+/// 参数：
+/// - listener: 已绑定的监听器
+/// - router: 服务器路由
+/// - options: 守护选项
+/// - shutdown_rx: 关闭信号接收器
+/// - listen_tx: 监听信息发送器，重启后更新地址
+/// - on_event: 生命周期事件通道
+pub(super) async fn supervise(
+    mut listener: TcpListener,
+    router: Router,
+    options: SupervisorOptions,
+    mut shutdown_rx: oneshot::Receiver<()>,
+    listen_tx: watch::Sender<ListenInfo>,
+    on_event: Channel<ServerEvent>,
+) {
+    let mut attempts = 0;
+
+    loop {
+        let started_at = Instant::now();
+        let (stop_tx, stop_rx) = oneshot::channel::<()>();
+        let service = router
+            .clone()
+            .into_make_service_with_connect_info::<SocketAddr>();
+
+        let mut serve_task = tokio::spawn(async move {
+            axum::serve(listener, service)
+                .with_graceful_shutdown(async {
+                    stop_rx.await.ok();
+                })
+                .await
+        });
+
+        let error = tokio::select! {
+            _ = &mut shutdown_rx => {
+                let _ = stop_tx.send(());
+                serve_task.await.ok();
+                let _ = on_event.send(ServerEvent::Stopped);
+                return;
+            }
+            result = &mut serve_task => match result {
+                Ok(Ok(())) => "服务器意外退出".to_string(),
+                Ok(Err(e)) => e.to_string(),
+                Err(e) => format!("服务器任务异常终止: {e}"),
+            },
+        };
+
+        log::error!("服务器异常退出: {error}");
+
+        if started_at.elapsed() >= RESTART_RESET_AFTER {
+            attempts = 0;
+        }
+
+        let restarting = options.auto_restart && attempts < MAX_RESTART_ATTEMPTS;
+        let _ = on_event.send(ServerEvent::Crashed {
+            error,
+            restarting,
+        });
+        if !restarting {
+            return;
+        }
+        attempts += 1;
+
+        // 等待后重启，等待期间仍可响应关闭信号
+        tokio::select! {
+            _ = &mut shutdown_rx => {
+                let _ = on_event.send(ServerEvent::Stopped);
+                return;
+            }
+            _ = tokio::time::sleep(RESTART_DELAY * attempts) => {}
+        }
+
+        let rebound = bind_listener(&options.host, options.port)
+            .await
+            .and_then(|listener| {
+                let addr = listener.local_addr()?;
+                Ok((listener, addr))
+            });
+
+        let addr = match rebound {
+            Ok((rebound_listener, addr)) => {
+                listener = rebound_listener;
+                addr
+            }
+            Err(e) => {
+                let _ = on_event.send(ServerEvent::Crashed {
+                    error: e.to_string(),
+                    restarting: false,
+                });
+                return;
+            }
+        };
+
+        let previous = listen_tx.send_replace(ListenInfo {
+            addr,
+            started_at: Instant::now(),
+        });
+
+        let url = format!("http://{addr}");
+        if addr.port() != previous.addr.port() {
+            let _ = on_event.send(ServerEvent::PortChanged {
+                requested: options.port,
+                actual: addr.port(),
+                url: url.clone(),
+            });
+        }
+        let _ = on_event.send(ServerEvent::Restarted {
+            url,
+        });
+
+        log::info!("服务器已重启: {addr}");
+    }
+}
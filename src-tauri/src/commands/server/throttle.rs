@@ -0,0 +1,175 @@
+// 网络模拟模块：为预览站点模拟慢速或不稳定的网络
+// 主要功能：
+// 1. 限速配置：按站点设置带宽上限、附加延迟和随机失败率
+// 2. 请求限速：在静态文件请求中应用延迟、失败和带宽限制
+// 3. 运行时切换：无需重启服务器即可修改或取消限速
+
+use std::time::Duration;
+
+use axum::{
+    body::{Body, Bytes},
+    http::StatusCode,
+    response::Response,
+};
+use futures::{stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use tauri::State as TauriState;
+use tokio::sync::Mutex;
+
+use super::ServerState;
+use crate::commands::{AppError, AppResult};
+
+/// 每秒发送的数据块数，带宽越低数据块越小，使加载进度更平滑
+const CHUNKS_PER_SECOND: u64 = 10;
+
+/// 数据块的最小字节数
+const MIN_CHUNK_SIZE: u64 = 512;
+
+/// 网络限速配置
+/// 包含：
+/// - bandwidth: 带宽上限（字节/秒），为 None 时不限速
+/// - latency: 每个请求的附加延迟（毫秒）
+/// - failure_rate: 请求随机失败的概率，取值 0 到 1
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ThrottleProfile {
+    bandwidth: Option<u64>,
+    #[serde(default)]
+    latency: u64,
+    #[serde(default)]
+    failure_rate: f64,
+}
+
+impl ThrottleProfile {
+    /// 校验配置取值
+    fn validate(&self) -> AppResult<()> {
+        if self.bandwidth == Some(0) {
+            return Err(AppError::Server("带宽上限必须大于 0".into()));
+        }
+        if !(0.0..=1.0).contains(&self.failure_rate) {
+            return Err(AppError::Server("失败率必须在 0 到 1 之间".into()));
+        }
+        Ok(())
+    }
+
+    /// 按失败率随机决定本次请求是否失败
+    fn should_fail(&self) -> bool {
+        if self.failure_rate <= 0.0 {
+            return false;
+        }
+        let mut bytes = [0u8; 4];
+        if getrandom::fill(&mut bytes).is_err() {
+            return false;
+        }
+        (u32::from_le_bytes(bytes) as f64 / u32::MAX as f64) < self.failure_rate
+    }
+
+    /// 在请求开始前应用限速配置
+    /// 命中随机失败时返回错误状态码，否则等待附加延迟
+    pub(super) async fn before_request(&self) -> Result<(), StatusCode> {
+        if self.should_fail() {
+            return Err(StatusCode::SERVICE_UNAVAILABLE);
+        }
+        if self.latency > 0 {
+            tokio::time::sleep(Duration::from_millis(self.latency)).await;
+        }
+        Ok(())
+    }
+
+    /// 对响应应用带宽限制
+    pub(super) fn limit_response(&self, response: Response) -> Response {
+        match self.bandwidth {
+            Some(bandwidth) => response.map(|body| limit_body(body, bandwidth)),
+            None => response,
+        }
+    }
+}
+
+/// 按带宽上限重新分块发送响应体
+/// 每个数据块发送前等待与其大小成比例的时间
+fn limit_body(body: Body, bandwidth: u64) -> Body {
+    let chunk_size = (bandwidth / CHUNKS_PER_SECOND).max(MIN_CHUNK_SIZE) as usize;
+
+    let chunks = body.into_data_stream().flat_map(move |result| {
+        let chunks: Vec<_> = match result {
+            Ok(bytes) => split_bytes(bytes, chunk_size).into_iter().map(Ok).collect(),
+            Err(e) => vec![Err(e)],
+        };
+        stream::iter(chunks)
+    });
+
+    let throttled = chunks.then(move |result| async move {
+        if let Ok(bytes) = &result {
+            let delay = Duration::from_secs_f64(bytes.len() as f64 / bandwidth as f64);
+            tokio::time::sleep(delay).await;
+        }
+        result
+    });
+
+    Body::from_stream(throttled)
+}
+
+/// 将字节切分为不超过指定大小的块
+fn split_bytes(mut bytes: Bytes, chunk_size: usize) -> Vec<Bytes> {
+    let mut chunks = Vec::with_capacity(bytes.len().div_ceil(chunk_size));
+    while bytes.len() > chunk_size {
+        chunks.push(bytes.split_to(chunk_size));
+    }
+    chunks.push(bytes);
+    chunks
+}
+
+/// 设置站点的网络限速配置
+/// 立即对之后的请求生效
+///
+/// 参数：
+/// - state: 服务器状态
+/// - path: 静态站点目录路径
+/// - profile: 限速配置，为 None 时取消限速
+///
+/// 返回：
+/// - 成功：空值
+/// - 失败：错误信息
+#[tauri::command]
+pub async fn set_site_throttle(
+    state: TauriState<'_, Mutex<ServerState>>,
+    path: String,
+    profile: Option<ThrottleProfile>,
+) -> AppResult<()> {
+    let state_guard = state.lock().await;
+    let (hash, _) = state_guard.resolve_site(&path)?;
+
+    let mut throttles = state_guard.app_state.throttles.write().await;
+    match profile {
+        Some(profile) => {
+            profile.validate()?;
+            throttles.insert(hash, profile);
+        }
+        None => {
+            throttles.remove(&hash);
+        }
+    }
+
+    Ok(())
+}
+
+/// 获取站点的网络限速配置
+///
+/// 参数：
+/// - state: 服务器状态
+/// - path: 静态站点目录路径
+///
+/// 返回：
+/// - 成功：限速配置，未限速时为 None
+/// - 失败：错误信息
+#[tauri::command]
+pub async fn get_site_throttle(
+    state: TauriState<'_, Mutex<ServerState>>,
+    path: String,
+) -> AppResult<Option<ThrottleProfile>> {
+    let state_guard = state.lock().await;
+    let (hash, _) = state_guard.resolve_site(&path)?;
+
+    let throttles = state_guard.app_state.throttles.read().await;
+    Ok(throttles.get(&hash).copied())
+}
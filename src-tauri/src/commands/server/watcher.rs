@@ -0,0 +1,251 @@
+// 文件监听模块：监听静态站点目录，文件变化时主动通知预览客户端
+// 主要功能：
+// 1. 目录监听：为每个静态站点创建文件系统监听器，同时监听站点目录和各附加目录
+// 2. 防抖合并：在短时间内的连续变化合并为一次通知
+// 3. 变化推送：按文件类型分类后，仅推送给正在预览该站点的客户端
+
+use std::{
+    collections::BTreeMap,
+    path::{Component, Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+use axum::extract::ws::Message;
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::Serialize;
+use tokio::{sync::mpsc, task::JoinHandle};
+
+use super::{
+    recorder::{Direction, Target},
+    AppState,
+};
+use crate::commands::{AppError, AppResult};
+
+/// 防抖间隔：在此时间内没有新的变化才发送通知
+const DEBOUNCE_INTERVAL: Duration = Duration::from_millis(300);
+
+/// WebGAL 重新拉取模板样式文件的调试指令
+const REFETCH_TEMPLATE_FILES_MESSAGE: &str = r#"{"event":"message","data":{"command":4}}"#;
+
+/// 文件变化类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FileChangeKind {
+    /// 场景文件：game/scene 下的文件
+    Scene,
+    /// 模板样式文件：game/template 下的文件
+    Template,
+    /// 游戏配置文件：game/config.txt
+    Config,
+    /// 游戏资源文件：game 下的其他文件，如立绘、背景、音频
+    Asset,
+    /// 引擎文件：game 目录之外的文件
+    Engine,
+}
+
+/// 单个文件变化
+/// - kind: 变化类型
+/// - path: 相对于站点根目录的路径，使用 `/` 分隔
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileChange {
+    kind: FileChangeKind,
+    path: String,
+}
+
+/// 推送给预览客户端的文件变化消息
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase", tag = "event", content = "data")]
+enum LiveReloadMessage {
+    FileChanged { changes: Vec<FileChange> },
+}
+
+/// 站点监听器
+/// 持有文件系统监听器和防抖任务，销毁时停止监听
+pub(super) struct SiteWatcher {
+    _watcher: RecommendedWatcher,
+    task: JoinHandle<()>,
+}
+
+impl Drop for SiteWatcher {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+impl SiteWatcher {
+    /// 开始监听站点目录及其附加目录
+    /// 附加目录中的文件按相对于该目录的路径推送，与分层查找时的站点路径一致
+    ///
+    /// 参数：
+    /// - state: 应用程序状态，用于查找站点的客户端
+    /// - hash: 站点哈希值
+    /// - root: 站点根目录（已标准化）
+    /// - layers: 附加目录（已标准化），无法监听的附加目录会被跳过
+    pub(super) fn spawn(
+        state: Arc<AppState>,
+        hash: String,
+        root: PathBuf,
+        layers: &[PathBuf],
+    ) -> AppResult<Self> {
+        let (path_tx, path_rx) = mpsc::unbounded_channel();
+
+        let mut watcher =
+            notify::recommended_watcher(move |result: notify::Result<notify::Event>| {
+                let Ok(event) = result else {
+                    return;
+                };
+                if matches!(event.kind, EventKind::Access(_)) {
+                    return;
+                }
+                for path in event.paths {
+                    let _ = path_tx.send(path);
+                }
+            })
+            .map_err(|e| AppError::Server(format!("无法创建文件监听器: {e}")))?;
+
+        watcher
+            .watch(&root, RecursiveMode::Recursive)
+            .map_err(|e| AppError::Server(format!("无法监听目录: {e}")))?;
+
+        let mut roots = vec![root];
+        for layer in layers {
+            match watcher.watch(layer, RecursiveMode::Recursive) {
+                Ok(()) => roots.push(layer.clone()),
+                Err(e) => log::warn!("无法监听附加目录 {}: {e}", layer.display()),
+            }
+        }
+
+        let task = tokio::spawn(debounce_and_notify(state, hash, roots, path_rx));
+
+        Ok(Self {
+            _watcher: watcher,
+            task,
+        })
+    }
+}
+
+/// 防抖并推送文件变化
+/// 收到第一个变化后开始计时，直到防抖间隔内没有新的变化，再合并推送
+async fn debounce_and_notify(
+    state: Arc<AppState>,
+    hash: String,
+    roots: Vec<PathBuf>,
+    mut path_rx: mpsc::UnboundedReceiver<PathBuf>,
+) {
+    while let Some(path) = path_rx.recv().await {
+        let mut pending = BTreeMap::new();
+        collect_change(&roots, &path, &mut pending);
+
+        loop {
+            match tokio::time::timeout(DEBOUNCE_INTERVAL, path_rx.recv()).await {
+                Ok(Some(path)) => collect_change(&roots, &path, &mut pending),
+                Ok(None) => return,
+                Err(_) => break,
+            }
+        }
+
+        if pending.is_empty() {
+            continue;
+        }
+
+        let changes: Vec<_> = pending
+            .into_iter()
+            .map(|(path, kind)| FileChange {
+                kind,
+                path,
+            })
+            .collect();
+        notify_site_clients(&state, &hash, &changes).await;
+    }
+}
+
+/// 将变化路径转换为相对于所在监听目录的路径并分类，忽略隐藏文件
+/// 监听目录互相嵌套时使用最内层的目录
+fn collect_change(roots: &[PathBuf], path: &Path, pending: &mut BTreeMap<String, FileChangeKind>) {
+    let Some(relative) = roots
+        .iter()
+        .filter_map(|root| path.strip_prefix(root).ok())
+        .min_by_key(|relative| relative.components().count())
+    else {
+        return;
+    };
+
+    let segments: Vec<_> = relative
+        .components()
+        .filter_map(|component| match component {
+            Component::Normal(segment) => segment.to_str(),
+            _ => None,
+        })
+        .collect();
+
+    if segments.is_empty() || segments.iter().any(|segment| segment.starts_with('.')) {
+        return;
+    }
+
+    pending.insert(segments.join("/"), classify(&segments));
+}
+
+/// 推送指定路径的文件变化，用于磁盘之外的变化来源（如内存覆盖层）
+///
+/// 参数：
+/// - state: 应用程序状态
+/// - hash: 站点哈希值
+/// - paths: 相对于站点根目录的路径，使用 `/` 分隔
+pub(super) async fn notify_paths_changed(state: &AppState, hash: &str, paths: &[String]) {
+    let changes: Vec<_> = paths
+        .iter()
+        .map(|path| {
+            let segments: Vec<_> = path.split('/').collect();
+            FileChange {
+                kind: classify(&segments),
+                path: path.clone(),
+            }
+        })
+        .collect();
+
+    if !changes.is_empty() {
+        notify_site_clients(state, hash, &changes).await;
+    }
+}
+
+/// 根据相对路径判断变化类型
+fn classify(segments: &[&str]) -> FileChangeKind {
+    match segments {
+        ["game", "scene", ..] => FileChangeKind::Scene,
+        ["game", "template", ..] => FileChangeKind::Template,
+        ["game", "config.txt"] => FileChangeKind::Config,
+        ["game", ..] => FileChangeKind::Asset,
+        _ => FileChangeKind::Engine,
+    }
+}
+
+/// 向站点房间推送文件变化
+/// 模板样式变化时额外发送 WebGAL 原生的重新拉取模板指令，使样式立即生效
+async fn notify_site_clients(state: &AppState, hash: &str, changes: &[FileChange]) {
+    let Ok(payload) = serde_json::to_string(&LiveReloadMessage::FileChanged {
+        changes: changes.to_vec(),
+    }) else {
+        return;
+    };
+
+    let refetch_templates = changes
+        .iter()
+        .any(|change| change.kind == FileChangeKind::Template);
+
+    let mut messages = vec![Message::Text(payload.into())];
+    if refetch_templates {
+        messages.push(Message::Text(REFETCH_TEMPLATE_FILES_MESSAGE.into()));
+    }
+
+    for message in messages {
+        state.sessions.lock().await.record(
+            Direction::FromServer,
+            Target::Site(hash.to_string()),
+            &message,
+        );
+        // 没有客户端在预览该站点时忽略
+        let _ = state.broadcast_to_room(hash, message).await;
+    }
+}
@@ -164,7 +164,7 @@ pub async fn get_thumbnail(
 
     // 保存到缓存
     if let Err(e) = fs::write(&cache_path, &buffer) {
-        eprintln!("写入缓存失败: {}", e);
+        log::warn!("写入缓存失败: {e}");
     }
 
     Ok(Response::new(buffer))
@@ -15,7 +15,8 @@ pub fn run() {
         #[cfg(debug_assertions)]
         _window.open_devtools();
 
-        app.manage(Mutex::new(ServerState::default()));
+        let site_registry_file = app.path().app_data_dir()?.join("sites.json");
+        app.manage(Mutex::new(ServerState::with_registry(site_registry_file)));
 
         Ok(())
     });
@@ -68,13 +69,63 @@ pub fn run() {
             // game
             commands::game::get_game_config,
             commands::game::set_game_config,
+            commands::game::get_game_config_version,
+            commands::game::get_game_config_schema,
+            commands::game::get_game_config_entries,
+            commands::game::validate_game_config,
+            commands::game::remove_game_config,
+            commands::game::rename_game_config_key,
+            commands::game::get_game_config_list,
+            commands::game::set_game_config_list,
+            // scene
+            commands::scene::parse_scene,
+            commands::scene::parse_game_scenes,
             // server
             commands::server::start_server,
+            commands::server::stop_server,
+            commands::server::get_server_status,
             commands::server::add_static_site,
             commands::server::remove_static_site,
+            commands::server::set_site_alias,
+            commands::server::list_sites,
             commands::server::broadcast_message,
+            commands::server::broadcast_to_site,
             commands::server::unicast_message,
             commands::server::get_connected_clients,
+            commands::server::backpressure::get_client_stats,
+            commands::server::backpressure::resync_client,
+            commands::server::recorder::start_session_recording,
+            commands::server::recorder::stop_session_recording,
+            commands::server::recorder::replay_session,
+            commands::server::recorder::stop_session_replay,
+            commands::server::saves::list_saves,
+            commands::server::saves::get_save,
+            commands::server::saves::delete_save,
+            commands::server::saves::import_save,
+            commands::server::inspector::get_state_snapshot,
+            commands::server::inspector::subscribe_state_snapshots,
+            commands::server::inspector::unsubscribe_state_snapshots,
+            commands::server::inspector::set_variable,
+            commands::server::lan::get_lan_address,
+            commands::server::lan::create_preview_session,
+            commands::server::lan::list_preview_sessions,
+            commands::server::lan::revoke_preview_session,
+            commands::server::origin::get_preview_url,
+            commands::server::overlay::put_overlay_file,
+            commands::server::overlay::update_overlay_file,
+            commands::server::overlay::clear_overlay_file,
+            commands::server::diagnostics::subscribe_request_errors,
+            commands::server::diagnostics::unsubscribe_request_errors,
+            commands::server::diagnostics::get_request_errors,
+            commands::server::diagnostics::get_missing_files,
+            commands::server::diagnostics::clear_request_errors,
+            commands::server::access_log::set_access_log_enabled,
+            commands::server::access_log::subscribe_access_log,
+            commands::server::access_log::unsubscribe_access_log,
+            commands::server::access_log::get_access_log,
+            commands::server::access_log::clear_access_log,
+            commands::server::throttle::set_site_throttle,
+            commands::server::throttle::get_site_throttle,
             // thumbnail
             commands::thumbnail::get_thumbnail,
             commands::thumbnail::get_image_dimensions,
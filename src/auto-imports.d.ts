@@ -88,6 +88,7 @@ declare global {
   const extractSpeakerChange: typeof import('./utils/speaker').extractSpeakerChange
   const fieldsToTransform: typeof import('./helper/effect-editor-config').fieldsToTransform
   const filterExtraArgs: typeof import('./helper/statement-editor/visibility').filterExtraArgs
+  const findConfigValue: typeof import('./commands/game').findConfigValue
   const formatFileSize: typeof import('./utils/format').formatFileSize
   const fsCmds: typeof import('./commands/fs').fsCmds
   const galleryEntries: typeof import('./helper/command-registry/gallery').galleryEntries
@@ -234,6 +235,7 @@ declare global {
   const roundByStep: typeof import('./helper/math').roundByStep
   const roundToPrecision: typeof import('./helper/math').roundToPrecision
   const safeInvoke: typeof import('./utils/invoke').safeInvoke
+  const sceneCmds: typeof import('./commands/scene').sceneCmds
   const sceneEntries: typeof import('./helper/command-registry/scene').sceneEntries
   const serializeCommandNode: typeof import('./helper/webgal-script/codec').serializeCommandNode
   const serializeEffectJson: typeof import('./helper/effect-editor-config').serializeEffectJson
@@ -738,6 +740,7 @@ declare module 'vue' {
     readonly extractSpeakerChange: UnwrapRef<typeof import('./utils/speaker')['extractSpeakerChange']>
     readonly fieldsToTransform: UnwrapRef<typeof import('./helper/effect-editor-config')['fieldsToTransform']>
     readonly filterExtraArgs: UnwrapRef<typeof import('./helper/statement-editor/visibility')['filterExtraArgs']>
+    readonly findConfigValue: UnwrapRef<typeof import('./commands/game')['findConfigValue']>
     readonly formatFileSize: UnwrapRef<typeof import('./utils/format')['formatFileSize']>
     readonly fsCmds: UnwrapRef<typeof import('./commands/fs')['fsCmds']>
     readonly galleryEntries: UnwrapRef<typeof import('./helper/command-registry/gallery')['galleryEntries']>
@@ -884,6 +887,7 @@ declare module 'vue' {
     readonly roundByStep: UnwrapRef<typeof import('./helper/math')['roundByStep']>
     readonly roundToPrecision: UnwrapRef<typeof import('./helper/math')['roundToPrecision']>
     readonly safeInvoke: UnwrapRef<typeof import('./utils/invoke')['safeInvoke']>
+    readonly sceneCmds: UnwrapRef<typeof import('./commands/scene')['sceneCmds']>
     readonly sceneEntries: UnwrapRef<typeof import('./helper/command-registry/scene')['sceneEntries']>
     readonly serializeCommandNode: UnwrapRef<typeof import('./helper/webgal-script/codec')['serializeCommandNode']>
     readonly serializeEffectJson: UnwrapRef<typeof import('./helper/effect-editor-config')['serializeEffectJson']>
@@ -7,7 +7,10 @@
  * @property gameKey - 游戏唯一标识键
  * @property packageName - 游戏包名
  * @property titleImg - 游戏标题图片路径
- * @property [key: string] - 其他可能的配置项
+ * @property [key: string] - 其他配置项，已知配置项为小驼峰命名，未知配置项保留原始键名
+ *
+ * 只有 WebGAL 支持的配置项（见 getGameConfigSchema）会转换为小驼峰命名，
+ * 未知配置项如 `Stage_Width` 不再转换为 `stageWidth`，读取时使用 findConfigValue
  */
 interface GameConfig {
   gameName: string
@@ -18,12 +21,177 @@ interface GameConfig {
   [key: string]: string
 }
 
+/**
+ * 读取配置项的值，兼容未知配置项保留原始键名
+ * @param config - 游戏配置
+ * @param name - 小驼峰命名的配置项名称，如 `stageWidth`
+ * @returns 配置项的值，先按名称查找，再查找转换为小驼峰命名后与名称相同的原始键名
+ */
+export function findConfigValue(config: GameConfig, name: string): string | undefined {
+  if (name in config) {
+    return config[name]
+  }
+  const key = Object.keys(config).find(key =>
+    key.toLowerCase().replace(/_(.)/g, (_, c: string) => c.toUpperCase()) === name,
+  )
+  return key === undefined ? undefined : config[key]
+}
+
+/**
+ * 配置项的值类型
+ */
+type ConfigKind = {
+  type: 'text'
+} | {
+  type: 'bool'
+} | {
+  type: 'choice'
+  options: string[]
+} | {
+  type: 'list'
+} | {
+  type: 'file'
+  dir: string
+} | {
+  type: 'fileList'
+  dir: string
+}
+
+/**
+ * 配置项定义
+ *
+ * @property key - 配置文件中的键名，区分大小写
+ * @property kind - 值类型
+ * @property required - 是否必填
+ */
+interface ConfigField {
+  key: string
+  kind: ConfigKind
+  required: boolean
+}
+
+/**
+ * 类型化的配置值，值不符合配置项定义时按文本返回
+ */
+type ConfigValue = {
+  type: 'text'
+  value: string
+} | {
+  type: 'bool'
+  value: boolean
+} | {
+  type: 'list'
+  value: string[]
+}
+
+/**
+ * 类型化的配置项
+ *
+ * @property key - 配置文件中的键名
+ * @property line - 所在行号（从 1 开始）
+ * @property value - 类型化的值
+ * @property kind - 配置项的值类型，未知的键为 null
+ */
+interface TypedEntry {
+  key: string
+  line: number
+  value: ConfigValue
+  kind: ConfigKind | null
+}
+
+/**
+ * 配置校验问题
+ *
+ * @property key - 相关的键名
+ * @property line - 所在行号，缺少必填项时为 null
+ * @property severity - 严重程度
+ * @property issue - 问题类型与详情
+ */
+interface ConfigIssue {
+  key: string
+  line: number | null
+  severity: 'error' | 'warning'
+  issue: {
+    type: 'missingKey'
+  } | {
+    type: 'duplicateKey'
+    firstLine: number
+  } | {
+    type: 'unknownKey'
+    suggestion: string | null
+  } | {
+    type: 'invalidBool'
+    value: string
+  } | {
+    type: 'invalidChoice'
+    value: string
+    options: string[]
+  } | {
+    type: 'missingFile'
+    path: string
+  }
+}
+
+/**
+ * 配置校验报告
+ *
+ * @property valid - 是否没有错误级别的问题
+ * @property issues - 按行号排序的问题列表
+ */
+interface ValidationReport {
+  valid: boolean
+  issues: ConfigIssue[]
+}
+
+/**
+ * 配置文件版本，写入配置时用于检测文件是否已被外部修改
+ *
+ * @property modified - 文件修改时间（Unix 毫秒）
+ * @property hash - 文件内容哈希
+ */
+export interface ConfigVersion {
+  modified: number | null
+  hash: string
+}
+
 async function getGameConfig(gamePath: string) {
   return safeInvoke<GameConfig>('get_game_config', { gamePath })
 }
 
-async function setGameConfig(gamePath: string, config: Record<string, string>) {
-  return safeInvoke<void>('set_game_config', { gamePath, config })
+async function setGameConfig(gamePath: string, config: Record<string, string>, expectedVersion?: ConfigVersion) {
+  return safeInvoke<ConfigVersion>('set_game_config', { gamePath, config, expectedVersion })
+}
+
+async function getGameConfigVersion(gamePath: string) {
+  return safeInvoke<ConfigVersion>('get_game_config_version', { gamePath })
+}
+
+async function getGameConfigSchema() {
+  return safeInvoke<ConfigField[]>('get_game_config_schema')
+}
+
+async function getGameConfigEntries(gamePath: string) {
+  return safeInvoke<TypedEntry[]>('get_game_config_entries', { gamePath })
+}
+
+async function validateGameConfig(gamePath: string) {
+  return safeInvoke<ValidationReport>('validate_game_config', { gamePath })
+}
+
+async function removeGameConfig(gamePath: string, keys: string[], expectedVersion?: ConfigVersion) {
+  return safeInvoke<ConfigVersion>('remove_game_config', { gamePath, keys, expectedVersion })
+}
+
+async function renameGameConfigKey(gamePath: string, from: string, to: string, expectedVersion?: ConfigVersion) {
+  return safeInvoke<ConfigVersion>('rename_game_config_key', { gamePath, from, to, expectedVersion })
+}
+
+async function getGameConfigList(gamePath: string, key: string) {
+  return safeInvoke<string[] | null>('get_game_config_list', { gamePath, key })
+}
+
+async function setGameConfigList(gamePath: string, key: string, items: string[], expectedVersion?: ConfigVersion) {
+  return safeInvoke<ConfigVersion>('set_game_config_list', { gamePath, key, items, expectedVersion })
 }
 
 async function runGameServer(gamePath: string) {
@@ -37,6 +205,14 @@ async function stopGameServer(gamePath: string) {
 export const gameCmds = {
   getGameConfig,
   setGameConfig,
+  getGameConfigVersion,
+  getGameConfigSchema,
+  getGameConfigEntries,
+  validateGameConfig,
+  removeGameConfig,
+  renameGameConfigKey,
+  getGameConfigList,
+  setGameConfigList,
   runGameServer,
   stopGameServer,
 }
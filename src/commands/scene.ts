@@ -0,0 +1,91 @@
+/**
+ * 源文本中的位置
+ *
+ * @property line - 行号，从 1 开始
+ * @property column - 列号，从 1 开始，按字符计数
+ */
+interface Position {
+  line: number
+  column: number
+}
+
+/**
+ * 源文本中的区间，end 指向区间之后的位置
+ */
+interface Span {
+  start: Position
+  end: Position
+}
+
+/**
+ * 语句参数
+ *
+ * @property key - 参数名
+ * @property value - 参数值，只有键名的参数（如 -next）为 true
+ * @property span - 参数的位置，不含前导的 " -"
+ */
+interface SceneArg {
+  key: string
+  value: string | number | boolean
+  span: Span
+}
+
+/**
+ * 后端解析的语句
+ *
+ * @property index - 语句序号，与 WebGAL 运行时的语句ID一致
+ * @property command - 命令名，如 say、changeBg、comment
+ * @property commandRaw - 冒号前的原始命令文本，角色简写时为角色名
+ * @property speaker - say 语句的说话人，旁白为空字符串，沿用上一位说话人时为 null
+ * @property content - 内容
+ * @property args - 参数
+ * @property inlineComment - 行内注释
+ * @property span - 整条语句的位置，多行语句包含所有接续行
+ * @property commandSpan - 命令的位置，无冒号的台词为 null
+ * @property contentSpan - 内容的位置
+ */
+interface SceneSentence {
+  index: number
+  command: string
+  commandRaw: string
+  speaker: string | null
+  content: string
+  args: SceneArg[]
+  inlineComment: string
+  span: Span
+  commandSpan: Span | null
+  contentSpan: Span
+}
+
+/**
+ * 后端解析的场景
+ *
+ * @property sentences - 语句列表，不含空行和接续行
+ */
+interface ParsedScene {
+  sentences: SceneSentence[]
+}
+
+/**
+ * 已解析的场景文件
+ *
+ * @property path - 相对于 game/scene/ 的路径
+ * @property scene - 解析结果
+ */
+interface SceneFile {
+  path: string
+  scene: ParsedScene
+}
+
+async function parseScene(content: string) {
+  return safeInvoke<ParsedScene>('parse_scene', { content })
+}
+
+async function parseGameScenes(gamePath: string) {
+  return safeInvoke<SceneFile[]>('parse_game_scenes', { gamePath })
+}
+
+export const sceneCmds = {
+  parseScene,
+  parseGameScenes,
+}
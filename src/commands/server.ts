@@ -1,48 +1,724 @@
 import { Channel, invoke } from '@tauri-apps/api/core'
 
+/**
+ * 局域网预览会话信息
+ *
+ * @property token - 访问令牌
+ * @property hash - 站点哈希
+ * @property url - 携带访问令牌的分享链接
+ * @property createdAt - 创建时间（Unix 毫秒）
+ */
+interface PreviewSessionInfo {
+  token: string
+  hash: string
+  url: string
+  createdAt: number
+}
+
+/**
+ * WebSocket 客户端信息
+ *
+ * @property id - 客户端ID，每个连接随机生成
+ * @property addr - 客户端地址（IP:端口）
+ * @property site - 所属站点哈希，无法识别时为 null
+ * @property userAgent - 客户端 User-Agent
+ * @property connectedAt - 连接时间（Unix 毫秒）
+ */
+interface ClientInfo {
+  id: string
+  addr: string
+  site: string | null
+  userAgent: string | null
+  connectedAt: number
+}
+
+/**
+ * 游戏发回的二进制数据
+ *
+ * @property kind - 数据类型，如 screenshot
+ * @property mime - 数据的 MIME 类型
+ * @property data - Base64 编码的数据
+ */
+interface BinaryFrame {
+  kind: string
+  mime: string | null
+  data: string
+}
+
+/**
+ * 编辑器桥接脚本的上报消息
+ */
+type BridgeReport = {
+  type: 'scene'
+  data: {
+    scene: string
+    sentence: number
+  }
+} | {
+  type: 'error'
+  data: {
+    message: string
+    source: string | null
+    line: number | null
+    column: number | null
+    stack: string | null
+  }
+} | {
+  type: 'console'
+  data: {
+    level: 'log' | 'info' | 'warn' | 'error' | 'debug'
+    args: string[]
+  }
+} | {
+  type: 'resources'
+  data: {
+    entries: {
+      name: string
+      initiatorType: string
+      duration: number
+      transferSize: number | null
+    }[]
+  }
+} | {
+  type: 'backlog'
+  data: {
+    entries: BacklogEntry[]
+  }
+}
+
+/**
+ * 回想记录条目
+ */
+interface BacklogEntry {
+  scene: string | null
+  sentence: number | null
+  speaker: string | null
+  text: string
+  vocal: string | null
+}
+
+/**
+ * 预览的运行时状态快照
+ *
+ * @property clientId - 客户端ID
+ * @property scene - 当前场景与语句
+ * @property variables - 游戏变量，取自舞台状态的 GameVar 字段
+ * @property backlog - 回想记录，由桥接脚本上报
+ * @property stage - 舞台状态
+ * @property updatedAt - 最近更新时间（Unix 毫秒）
+ */
+interface StateSnapshot {
+  clientId: string
+  scene: { scene: string, sentence: number } | null
+  variables: Record<string, unknown>
+  backlog: BacklogEntry[]
+  stage: unknown
+  updatedAt: number
+}
+
+/**
+ * 客户端消息信封，携带发送方身份
+ * 消息已由服务器按 WebGAL 调试协议校验，无法解析的消息以 invalid 类型连同原因转发
+ */
+interface ClientMessage {
+  client: ClientInfo
+  message: {
+    type: 'debug'
+    data: DebugMessage
+  } | {
+    type: 'binary'
+    data: BinaryFrame
+  } | {
+    type: 'bridge'
+    data: BridgeReport
+  } | {
+    type: 'invalid'
+    data: {
+      raw: string | null
+      error: string
+    }
+  }
+}
+
+/**
+ * 服务器生命周期事件
+ */
+export type ServerEvent = {
+  event: 'portChanged'
+  data: {
+    requested: number
+    actual: number
+    url: string
+  }
+} | {
+  event: 'crashed'
+  data: {
+    error: string
+    restarting: boolean
+  }
+} | {
+  event: 'restarted'
+  data: {
+    url: string
+  }
+} | {
+  event: 'stopped'
+} | {
+  event: 'clientConnected'
+  data: {
+    client: ClientInfo
+  }
+} | {
+  event: 'clientDisconnected'
+  data: {
+    client: ClientInfo
+    reason: DisconnectReason
+  }
+} | {
+  event: 'clientLagged'
+  data: {
+    clientId: string
+    skipped: number
+  }
+}
+
+/**
+ * WebSocket 客户端断开原因：主动关闭、心跳超时或连接异常
+ */
+type DisconnectReason = 'closed' | 'timeout' | 'error'
+
+/**
+ * WebSocket 心跳配置
+ *
+ * @property interval - Ping 发送间隔（秒），默认 15
+ * @property timeout - 超过该时长（秒）未收到客户端任何消息时断开连接，默认 45
+ */
+interface HeartbeatOptions {
+  interval?: number
+  timeout?: number
+}
+
+/**
+ * 站点选项
+ *
+ * @property gzip - 是否启用 gzip 压缩
+ * @property brotli - 是否启用 brotli 压缩
+ * @property crossOriginIsolation - 是否发送 COOP/COEP 响应头，使 SharedArrayBuffer 可用
+ * @property cors - 是否允许任意来源跨域访问
+ * @property cache - 缓存模式：禁止缓存、每次协商或模拟生产环境
+ * @property bridge - 是否向站点首页注入编辑器桥接脚本，上报场景、错误、控制台与资源耗时
+ * @property persistSaves - 是否将预览存档同步到项目的 .webgal-craft/saves/ 目录
+ */
+interface SiteOptions {
+  gzip?: boolean
+  brotli?: boolean
+  crossOriginIsolation?: boolean
+  cors?: boolean
+  cache?: 'noStore' | 'revalidate' | 'production'
+  bridge?: boolean
+  persistSaves?: boolean
+}
+
+/**
+ * 服务器运行状态
+ *
+ * @property running - 是否正在运行
+ * @property url - 服务器访问地址
+ * @property uptime - 本次监听已运行的秒数
+ * @property sites - 已注册的静态站点
+ * @property clientCount - 已连接的客户端数量
+ */
+interface ServerStatus {
+  running: boolean
+  url: string | null
+  uptime: number
+  sites: { hash: string, path: string, layers: string[], options: SiteOptions }[]
+  clientCount: number
+}
+
+/**
+ * 站点注册表条目
+ *
+ * @property hash - 由站点目录计算的稳定哈希
+ * @property alias - 站点别名，设置后代替哈希作为站点ID
+ * @property path - 站点目录
+ */
+interface SiteEntry {
+  hash: string
+  alias: string | null
+  path: string
+}
+
+/**
+ * 预览站点的失败请求（状态码为 4xx/5xx 的响应）
+ *
+ * @property site - 站点ID
+ * @property path - 相对于游戏目录的请求路径
+ * @property status - 响应状态码
+ * @property referer - 发起请求的页面
+ * @property time - 请求时间（Unix 毫秒）
+ */
+interface RequestError {
+  site: string
+  path: string
+  status: number
+  referer: string | null
+  time: number
+}
+
+/**
+ * 预览站点的缺失文件
+ *
+ * @property path - 相对于游戏目录的文件路径
+ * @property count - 请求次数
+ * @property referers - 请求过该文件的页面
+ * @property firstSeen - 首次请求时间（Unix 毫秒）
+ * @property lastSeen - 最近请求时间（Unix 毫秒）
+ */
+interface MissingFile {
+  path: string
+  count: number
+  referers: string[]
+  firstSeen: number
+  lastSeen: number
+}
+
+/**
+ * 访问日志条目
+ *
+ * @property id - 自增序号
+ * @property method - 请求方法
+ * @property path - 请求路径
+ * @property site - 站点ID，非站点请求时为 null
+ * @property status - 响应状态码
+ * @property bytes - 已发送的响应体字节数，客户端提前断开时为断开前发送的字节数
+ * @property latencyMs - 从收到请求到响应体发送完毕（或连接断开）的耗时（毫秒）
+ * @property time - 请求时间（Unix 毫秒）
+ */
+interface AccessLogEntry {
+  id: number
+  method: string
+  path: string
+  site: string | null
+  status: number
+  bytes: number
+  latencyMs: number
+  time: number
+}
+
+/**
+ * 网络限速配置
+ *
+ * @property bandwidth - 带宽上限（字节/秒），为 null 时不限速
+ * @property latency - 每个请求的附加延迟（毫秒）
+ * @property failureRate - 请求随机失败的概率，取值 0 到 1
+ */
+interface ThrottleProfile {
+  bandwidth: number | null
+  latency: number
+  failureRate: number
+}
+
+/**
+ * 客户端消息统计
+ *
+ * @property clientId - 客户端ID
+ * @property lagged - 因处理过慢而跳过的广播消息数
+ * @property dropped - 因队列已满而丢弃的单播消息数
+ */
+interface ClientQueueStats {
+  clientId: string
+  lagged: number
+  dropped: number
+}
+
+/**
+ * 预览存档信息
+ *
+ * @property key - 存档键，即 WebGAL 在浏览器存储中使用的键
+ * @property size - 存档文件字节数
+ * @property modified - 存档文件修改时间（Unix 毫秒）
+ * @property saveTime - WebGAL 记录的存档时间
+ * @property scene - 存档所在的场景
+ * @property sentence - 存档所在的语句序号
+ */
+interface SaveSlot {
+  key: string
+  size: number
+  modified: number | null
+  saveTime: string | null
+  scene: string | null
+  sentence: number | null
+}
+
+/**
+ * 会话录制结果
+ *
+ * @property path - 录制文件路径
+ * @property messages - 录制的消息数
+ * @property durationMs - 录制时长（毫秒）
+ */
+interface RecordingSummary {
+  path: string
+  messages: number
+  durationMs: number
+}
+
+/**
+ * 会话重放进度事件
+ */
+type ReplayEvent = {
+  event: 'progress'
+  data: {
+    sent: number
+    total: number
+  }
+} | {
+  event: 'finished'
+  data: {
+    sent: number
+  }
+}
+
 /**
  * 启动静态文件服务器
+ * @param onEvent - 服务器事件回调，端口变化、意外退出或客户端连接状态变化时触发
+ * @param autoRestart - 意外退出后是否自动重启
+ * @param heartbeat - WebSocket 心跳配置
  */
-async function startServer(host: string, port: number): Promise<string> {
+async function startServer(
+  host: string,
+  port: number,
+  onEvent?: (event: ServerEvent) => void,
+  autoRestart?: boolean,
+  heartbeat?: HeartbeatOptions,
+): Promise<string> {
   try {
-    const channel = new Channel<string>()
+    const channel = new Channel<ClientMessage>()
     channel.onmessage = async (_message) => { /* no-op */ }
 
+    const eventChannel = new Channel<ServerEvent>()
+    eventChannel.onmessage = (event) => {
+      onEvent?.(event)
+    }
+
     return await invoke<string>('start_server', {
       host,
       port,
+      autoRestart,
+      heartbeat,
       onMessage: channel,
+      onEvent: eventChannel,
     })
   } catch (error) {
     throw AppError.fromInvoke('start_server', error)
   }
 }
 
-async function addStaticSite(path: string): Promise<string> {
-  return safeInvoke<string>('add_static_site', { path })
+async function stopServer(): Promise<void> {
+  return safeInvoke<void>('stop_server')
+}
+
+async function getServerStatus(): Promise<ServerStatus> {
+  return safeInvoke<ServerStatus>('get_server_status')
+}
+
+/**
+ * 添加静态站点
+ * @param alias - 站点别名，省略时保留已有别名
+ * @param options - 站点选项，省略时保留已有选项
+ * @param layers - 在站点目录之后依次查找的目录（如共享的引擎目录），省略时保留已有设置
+ * @returns 站点ID
+ */
+async function addStaticSite(
+  path: string,
+  alias?: string,
+  options?: SiteOptions,
+  layers?: string[],
+): Promise<string> {
+  return safeInvoke<string>('add_static_site', { path, alias, options, layers })
 }
 
 async function removeStaticSite(path: string): Promise<void> {
   return safeInvoke<void>('remove_static_site', { path })
 }
 
-async function broadcastMessage(message: string): Promise<void> {
+/**
+ * 设置站点别名
+ * @param alias - 新别名，传入 null 时恢复使用哈希
+ * @returns 新的站点ID
+ */
+async function setSiteAlias(path: string, alias: string | null): Promise<string> {
+  return safeInvoke<string>('set_site_alias', { path, alias })
+}
+
+async function listSites(): Promise<SiteEntry[]> {
+  return safeInvoke<SiteEntry[]>('list_sites')
+}
+
+/**
+ * 获取站点的预览地址
+ * 本机预览时使用 `{站点ID}.localhost` 子域名，每个游戏拥有独立的存储空间
+ */
+async function getPreviewUrl(path: string): Promise<string> {
+  return safeInvoke<string>('get_preview_url', { path })
+}
+
+/**
+ * 放入内存覆盖层文件，预览将使用此内容代替磁盘上的文件
+ * @param path - 游戏目录
+ * @param file - 相对于游戏目录的文件路径，如 game/scene/start.txt
+ */
+async function putOverlayFile(path: string, file: string, content: string): Promise<void> {
+  return safeInvoke<void>('put_overlay_file', { path, file, content })
+}
+
+/**
+ * 更新已放入内存覆盖层的文件
+ */
+async function updateOverlayFile(path: string, file: string, content: string): Promise<void> {
+  return safeInvoke<void>('update_overlay_file', { path, file, content })
+}
+
+/**
+ * 清除内存覆盖层文件，恢复使用磁盘上的文件
+ * @param file - 相对于游戏目录的文件路径，省略时清除该游戏的全部覆盖层文件
+ */
+async function clearOverlayFile(path: string, file?: string): Promise<void> {
+  return safeInvoke<void>('clear_overlay_file', { path, file })
+}
+
+/**
+ * 订阅预览站点的失败请求
+ * @param onError - 失败请求回调，任一站点返回 4xx/5xx 状态码时触发
+ * @returns 退订函数
+ */
+async function subscribeRequestErrors(onError: (error: RequestError) => void): Promise<() => Promise<void>> {
+  const channel = new Channel<RequestError>()
+  channel.onmessage = onError
+  await safeInvoke<void>('subscribe_request_errors', { onError: channel })
+  return () => safeInvoke<void>('unsubscribe_request_errors', { channelId: channel.id })
+}
+
+async function getRequestErrors(path: string): Promise<RequestError[]> {
+  return safeInvoke<RequestError[]>('get_request_errors', { path })
+}
+
+async function getMissingFiles(path: string): Promise<MissingFile[]> {
+  return safeInvoke<MissingFile[]>('get_missing_files', { path })
+}
+
+async function clearRequestErrors(path: string): Promise<void> {
+  return safeInvoke<void>('clear_request_errors', { path })
+}
+
+async function setAccessLogEnabled(enabled: boolean): Promise<void> {
+  return safeInvoke<void>('set_access_log_enabled', { enabled })
+}
+
+/**
+ * 订阅访问日志，需先通过 setAccessLogEnabled 开启
+ * @returns 退订函数
+ */
+async function subscribeAccessLog(onEntry: (entry: AccessLogEntry) => void): Promise<() => Promise<void>> {
+  const channel = new Channel<AccessLogEntry>()
+  channel.onmessage = onEntry
+  await safeInvoke<void>('subscribe_access_log', { onEntry: channel })
+  return () => safeInvoke<void>('unsubscribe_access_log', { channelId: channel.id })
+}
+
+/**
+ * 查询访问日志
+ * @param path - 游戏目录，指定时只返回该游戏的记录
+ * @param limit - 最多返回的条数
+ */
+async function getAccessLog(path?: string, limit?: number): Promise<AccessLogEntry[]> {
+  return safeInvoke<AccessLogEntry[]>('get_access_log', { path, limit })
+}
+
+async function clearAccessLog(): Promise<void> {
+  return safeInvoke<void>('clear_access_log')
+}
+
+/**
+ * 设置游戏预览的网络限速配置，立即生效
+ * @param profile - 限速配置，传入 null 时取消限速
+ */
+async function setSiteThrottle(path: string, profile: ThrottleProfile | null): Promise<void> {
+  return safeInvoke<void>('set_site_throttle', { path, profile })
+}
+
+async function getSiteThrottle(path: string): Promise<ThrottleProfile | null> {
+  return safeInvoke<ThrottleProfile | null>('get_site_throttle', { path })
+}
+
+async function broadcastMessage(message: DebugMessage): Promise<void> {
   return safeInvoke<void>('broadcast_message', { message })
 }
 
-async function unicastMessage(clientAddr: string, message: string): Promise<void> {
-  return safeInvoke<void>('unicast_message', { clientAddr, message })
+async function broadcastToSite(path: string, message: DebugMessage): Promise<void> {
+  return safeInvoke<void>('broadcast_to_site', { path, message })
+}
+
+async function unicastMessage(clientId: string, message: DebugMessage): Promise<void> {
+  return safeInvoke<void>('unicast_message', { clientId, message })
+}
+
+async function getConnectedClients(): Promise<ClientInfo[]> {
+  return safeInvoke<ClientInfo[]>('get_connected_clients')
+}
+
+async function getClientStats(): Promise<ClientQueueStats[]> {
+  return safeInvoke<ClientQueueStats[]>('get_client_stats')
+}
+
+/**
+ * 向客户端重放最近的场景跳转，使落后的客户端恢复同步
+ */
+async function resyncClient(clientId: string): Promise<void> {
+  return safeInvoke<void>('resync_client', { clientId })
+}
+
+/**
+ * 开始录制 /api/webgalsync 会话
+ * @param dir - 录制文件所在目录
+ * @returns 录制文件路径
+ */
+async function startSessionRecording(dir: string): Promise<string> {
+  return safeInvoke<string>('start_session_recording', { dir })
+}
+
+async function stopSessionRecording(): Promise<RecordingSummary | null> {
+  return safeInvoke<RecordingSummary | null>('stop_session_recording')
+}
+
+/**
+ * 重放录制的会话
+ * @param file - 录制文件路径
+ * @param path - 目标站点目录，指定时所有消息都发往该站点
+ * @param speed - 重放速度倍率，默认 1
+ * @param onEvent - 重放进度回调
+ * @returns 待重放的消息数
+ */
+async function replaySession(
+  file: string,
+  path?: string,
+  speed?: number,
+  onEvent?: (event: ReplayEvent) => void,
+): Promise<number> {
+  const channel = new Channel<ReplayEvent>()
+  channel.onmessage = (event) => {
+    onEvent?.(event)
+  }
+  return safeInvoke<number>('replay_session', { file, path, speed, onEvent: channel })
+}
+
+async function stopSessionReplay(): Promise<void> {
+  return safeInvoke<void>('stop_session_replay')
+}
+
+async function getStateSnapshot(clientId: string): Promise<StateSnapshot | null> {
+  return safeInvoke<StateSnapshot | null>('get_state_snapshot', { clientId })
+}
+
+/**
+ * 订阅预览的运行时状态快照
+ * @param onSnapshot - 任一客户端状态变化时的回调，参数为该客户端的完整快照
+ * @returns 退订函数
+ */
+async function subscribeStateSnapshots(onSnapshot: (snapshot: StateSnapshot) => void): Promise<() => Promise<void>> {
+  const channel = new Channel<StateSnapshot>()
+  channel.onmessage = onSnapshot
+  await safeInvoke<void>('subscribe_state_snapshots', { onSnapshot: channel })
+  return () => safeInvoke<void>('unsubscribe_state_snapshots', { channelId: channel.id })
+}
+
+/**
+ * 设置预览中的变量
+ * @param value - WebGAL 表达式形式的变量值，如 `10`、`true`、`a+1`
+ */
+async function setVariable(clientId: string, name: string, value: string): Promise<void> {
+  return safeInvoke<void>('set_variable', { clientId, name, value })
+}
+
+async function listSaves(path: string): Promise<SaveSlot[]> {
+  return safeInvoke<SaveSlot[]>('list_saves', { path })
+}
+
+async function getSave(path: string, key: string): Promise<unknown> {
+  return safeInvoke<unknown>('get_save', { path, key })
+}
+
+async function deleteSave(path: string, key: string): Promise<void> {
+  return safeInvoke<void>('delete_save', { path, key })
+}
+
+/**
+ * 导入存档文件到项目
+ * @param file - 待导入的存档文件路径
+ * @param key - 存档键，省略时使用文件名
+ */
+async function importSave(path: string, file: string, key?: string): Promise<SaveSlot> {
+  return safeInvoke<SaveSlot>('import_save', { path, file, key })
+}
+
+async function getLanAddress(): Promise<string | null> {
+  return safeInvoke<string | null>('get_lan_address')
+}
+
+async function createPreviewSession(path: string): Promise<PreviewSessionInfo> {
+  return safeInvoke<PreviewSessionInfo>('create_preview_session', { path })
+}
+
+async function listPreviewSessions(): Promise<PreviewSessionInfo[]> {
+  return safeInvoke<PreviewSessionInfo[]>('list_preview_sessions')
 }
 
-async function getConnectedClients(): Promise<string[]> {
-  return safeInvoke<string[]>('get_connected_clients')
+async function revokePreviewSession(token: string): Promise<void> {
+  return safeInvoke<void>('revoke_preview_session', { token })
 }
 
 export const serverCmds = {
   startServer,
+  stopServer,
+  getServerStatus,
   addStaticSite,
   removeStaticSite,
+  setSiteAlias,
+  listSites,
+  getPreviewUrl,
+  putOverlayFile,
+  updateOverlayFile,
+  clearOverlayFile,
+  subscribeRequestErrors,
+  getRequestErrors,
+  getMissingFiles,
+  clearRequestErrors,
+  setAccessLogEnabled,
+  subscribeAccessLog,
+  getAccessLog,
+  clearAccessLog,
+  setSiteThrottle,
+  getSiteThrottle,
   broadcastMessage,
+  broadcastToSite,
   unicastMessage,
   getConnectedClients,
+  getClientStats,
+  resyncClient,
+  startSessionRecording,
+  stopSessionRecording,
+  replaySession,
+  stopSessionReplay,
+  getStateSnapshot,
+  subscribeStateSnapshots,
+  setVariable,
+  listSaves,
+  getSave,
+  deleteSave,
+  importSave,
+  getLanAddress,
+  createPreviewSession,
+  listPreviewSessions,
+  revokePreviewSession,
 }
@@ -1,20 +1,70 @@
+import type { ConfigVersion } from '~/commands/game'
+
+/**
+ * 各游戏最近一次读取或写入的配置文件版本，键为游戏路径
+ * 写入时携带该版本，配置文件已被外部修改时后端返回冲突错误，需重新读取后再保存
+ */
+const versions = new Map<string, ConfigVersion>()
+
 /**
- * 获取游戏配置
+ * 记录写入后的配置文件版本，并更新游戏的修改时间
+ * @param gamePath 游戏路径
+ * @param version 写入后的配置文件版本
+ */
+function recordWrite(gamePath: string, version: ConfigVersion) {
+  versions.set(gamePath, version)
+  gameManager.updateCurrentGameLastModified()
+}
+
+/**
+ * 获取游戏配置，并记录配置文件版本
  * @param gamePath 游戏路径
  * @returns 游戏配置对象
  */
 async function getConfig(gamePath: string) {
-  return await gameCmds.getGameConfig(gamePath)
+  // 先读取版本再读取配置：两次读取之间文件被修改时，记录的版本偏旧，下次写入报告冲突而不是覆盖外部修改
+  const version = await gameCmds.getGameConfigVersion(gamePath)
+  const config = await gameCmds.getGameConfig(gamePath)
+  versions.set(gamePath, version)
+  return config
 }
 
 /**
  * 设置游戏配置
  * @param gamePath 游戏路径
- * @param config 配置对象
+ * @param config 配置对象，已知配置项使用小驼峰命名，未知配置项使用 getConfig 返回的原始键名
  */
 async function setConfig(gamePath: string, config: Record<string, string>) {
-  await gameCmds.setGameConfig(gamePath, config)
-  gameManager.updateCurrentGameLastModified()
+  recordWrite(gamePath, await gameCmds.setGameConfig(gamePath, config, versions.get(gamePath)))
+}
+
+/**
+ * 删除游戏配置项
+ * @param gamePath 游戏路径
+ * @param keys 配置文件中的键名
+ */
+async function removeConfig(gamePath: string, keys: string[]) {
+  recordWrite(gamePath, await gameCmds.removeGameConfig(gamePath, keys, versions.get(gamePath)))
+}
+
+/**
+ * 重命名游戏配置项
+ * @param gamePath 游戏路径
+ * @param from 原键名
+ * @param to 新键名
+ */
+async function renameConfigKey(gamePath: string, from: string, to: string) {
+  recordWrite(gamePath, await gameCmds.renameGameConfigKey(gamePath, from, to, versions.get(gamePath)))
+}
+
+/**
+ * 设置列表值的游戏配置项
+ * @param gamePath 游戏路径
+ * @param key 配置文件中的键名
+ * @param items 列表项
+ */
+async function setConfigList(gamePath: string, key: string, items: string[]) {
+  recordWrite(gamePath, await gameCmds.setGameConfigList(gamePath, key, items, versions.get(gamePath)))
 }
 
 /**
@@ -23,4 +73,7 @@ async function setConfig(gamePath: string, config: Record<string, string>) {
 export const configManager = {
   getConfig,
   setConfig,
+  removeConfig,
+  renameConfigKey,
+  setConfigList,
 }
@@ -2,6 +2,7 @@ import { normalize, sep } from '@tauri-apps/api/path'
 
 /**
  * 发送调试命令到游戏
+ * 有打开的游戏时仅发送给预览该游戏的客户端，否则广播给所有客户端
  * @param data - 要发送的命令数据
  * @param event - 可选的事件类型，默认为 'message'
  */
@@ -13,7 +14,11 @@ async function sendCommand<T extends DebugCommand>(
     event: event ?? 'message',
     data,
   }
-  await serverCmds.broadcastMessage(JSON.stringify(message))
+  const workspaceStore = useWorkspaceStore()
+  const gamePath = workspaceStore.CWD
+  await (gamePath
+    ? serverCmds.broadcastToSite(gamePath, message)
+    : serverCmds.broadcastMessage(message))
 }
 
 /**
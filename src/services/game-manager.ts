@@ -134,13 +134,25 @@ async function importGame(gamePath: string): Promise<string> {
   return await registerGame(gamePath)
 }
 
+/** 等待预览服务器就绪的最长时间（毫秒） */
+const SERVER_READY_TIMEOUT = 10_000
+
 /**
  * 运行游戏预览
+ * 等待预览服务器就绪，服务器启动失败、异常退出或等待超时时抛出错误
  * @param gamePath 游戏路径
  */
 async function runGamePreview(gamePath: string) {
-  const id = await serverCmds.addStaticSite(gamePath)
-  return `http://localhost:8899/game/${id}/`
+  const workspaceStore = useWorkspaceStore()
+  await until(() => !!workspaceStore.serverUrl || !!workspaceStore.serverError)
+    .toBe(true, { timeout: SERVER_READY_TIMEOUT })
+  if (!workspaceStore.serverUrl) {
+    throw new AppError('SERVER_ERROR', workspaceStore.serverError
+      ? `预览服务器不可用: ${workspaceStore.serverError}`
+      : '等待预览服务器启动超时')
+  }
+  await serverCmds.addStaticSite(gamePath)
+  return await serverCmds.getPreviewUrl(gamePath)
 }
 
 /**
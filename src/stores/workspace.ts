@@ -2,6 +2,8 @@ import { defineStore } from 'pinia'
 
 import { db } from '~/database/db'
 
+import type { ServerEvent } from '~/commands/server'
+
 export const useWorkspaceStore = defineStore(
   'workspace',
   () => {
@@ -10,6 +12,8 @@ export const useWorkspaceStore = defineStore(
 
     // 服务器状态
     let serverUrl = $ref<string>()
+    // 服务器启动失败或异常退出且不再重启时的错误信息
+    let serverError = $ref<string>()
     let currentGameServeUrl = $ref<string>()
 
     // UI 状态
@@ -21,9 +25,68 @@ export const useWorkspaceStore = defineStore(
 
     async function runServer() {
       try {
-        serverUrl = await serverCmds.startServer('127.0.0.1', 8899)
+        serverUrl = await serverCmds.startServer('127.0.0.1', 8899, handleServerEvent, true)
+        serverError = undefined
       } catch (error) {
         logger.error(`服务器启动失败: ${error}`)
+        serverError = String(error)
+      }
+    }
+
+    async function handleServerEvent(event: ServerEvent) {
+      switch (event.event) {
+        case 'portChanged': {
+          logger.warn(`服务器端口已变更: ${event.data.requested} -> ${event.data.actual}`)
+          serverUrl = event.data.url
+          serverError = undefined
+          if (currentGame && currentGameServeUrl) {
+            currentGameServeUrl = await gameManager.runGamePreview(currentGame.path)
+          }
+          break
+        }
+        case 'crashed': {
+          logger.error(`服务器异常退出: ${event.data.error}`)
+          if (!event.data.restarting) {
+            serverUrl = undefined
+            serverError = event.data.error
+          }
+          break
+        }
+        case 'restarted': {
+          logger.info(`服务器已重启: ${event.data.url}`)
+          serverUrl = event.data.url
+          serverError = undefined
+          break
+        }
+        case 'stopped': {
+          serverUrl = undefined
+          break
+        }
+        case 'clientConnected': {
+          logger.info(`预览客户端已连接: ${event.data.client.id}`)
+          break
+        }
+        case 'clientDisconnected': {
+          const { client, reason } = event.data
+          if (reason === 'timeout') {
+            logger.warn(`预览客户端心跳超时，已断开: ${client.id}`)
+          } else {
+            logger.info(`预览客户端已断开: ${client.id}`)
+          }
+          break
+        }
+        case 'clientLagged': {
+          logger.warn(`预览客户端落后 ${event.data.skipped} 条消息，正在重新同步: ${event.data.clientId}`)
+          try {
+            await serverCmds.resyncClient(event.data.clientId)
+          } catch (error) {
+            logger.warn(`预览客户端重新同步失败: ${error}`)
+          }
+          break
+        }
+        default: {
+          break
+        }
       }
     }
 
@@ -70,6 +133,7 @@ export const useWorkspaceStore = defineStore(
       currentGame,
       currentGameServeUrl,
       serverUrl,
+      serverError,
       CWD,
       refreshGameMetadata,
       runServer,
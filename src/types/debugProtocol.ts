@@ -22,6 +22,14 @@ interface JumpMessageData extends DebugMessageBase<DebugCommand.JUMP> {
   message: 'exp' | 'sync'
 }
 
+interface SyncFromClientMessageData extends DebugMessageBase<DebugCommand.SYNCFC> {
+  sceneMsg: {
+    sentence: number
+    scene: string
+  }
+  stageSyncMsg?: unknown // 游戏的舞台状态
+}
+
 interface ExecuteCommandMessageData extends DebugMessageBase<DebugCommand.EXE_COMMAND> {
   message: string
 }
@@ -46,6 +54,7 @@ interface SetEffectMessageData extends DebugMessageBase<DebugCommand.SET_EFFECT>
 
 interface DebugMessageDataMap {
   [DebugCommand.JUMP]: JumpMessageData
+  [DebugCommand.SYNCFC]: SyncFromClientMessageData
   [DebugCommand.EXE_COMMAND]: ExecuteCommandMessageData
   [DebugCommand.SET_COMPONENT_VISIBILITY]: ComponentVisibilityMessageData
   [DebugCommand.TEMP_SCENE]: TempSceneMessageData
@@ -4,6 +4,7 @@ type BackendErrorCode =
   | 'IMAGE_ERROR'
   | 'SERVER_ERROR'
   | 'CONFIG_ERROR'
+  | 'CONFLICT_ERROR'
   | 'WINDOW_ERROR'
   | 'TAURI_ERROR'
 
@@ -34,7 +35,7 @@ export function isBackendError(value: unknown): value is BackendError {
 
 /** 用于运行时校验后端返回的错误码 */
 const VALID_ERROR_CODES: ReadonlySet<string> = new Set<ErrorCode>([
-  'IO_ERROR', 'IMAGE_ERROR', 'SERVER_ERROR', 'CONFIG_ERROR', 'WINDOW_ERROR', 'TAURI_ERROR',
+  'IO_ERROR', 'IMAGE_ERROR', 'SERVER_ERROR', 'CONFIG_ERROR', 'CONFLICT_ERROR', 'WINDOW_ERROR', 'TAURI_ERROR',
   'UNKNOWN', 'DIR_NOT_FOUND', 'PATH_TRAVERSAL', 'FS_ERROR', 'EDITOR_ERROR', 'INVALID_STRUCTURE',
 ])
 